@@ -1,6 +1,9 @@
 use packed_struct::prelude::*;
 
-use crate::Vec3;
+use crate::{
+    calibration,
+    Vec3,
+};
 
 #[derive(
     Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, Hash, PackedStruct,
@@ -37,23 +40,26 @@ impl Payload {
     #[inline]
     pub fn display(&self) -> String {
         let &Payload {
-            pcb_temp,
-            battery_temp,
-            battery_voltage,
             gyro,
             accelerometer,
-            calipile_object,
-            calipile_ambient,
             orientation,
             steer,
             ..
         } = self;
 
+        let cal = calibration::current();
+
+        let calipile_object = cal.calipile_object(self);
+        let calipile_ambient = cal.calipile_ambient(self);
+        let pcb_temp = cal.pcb_temp(self);
+        let battery_temp = cal.battery_temp(self);
+        let battery_voltage = cal.battery_voltage(self);
+
         let accel = accelerometer.display();
         let gyro = gyro.display();
 
         format!(
-            "tp: {calipile_object} obj / {calipile_ambient} amb, accel: {accel}, gyro: {gyro}, steer: {steer}, orient: {orientation}, pcb: {pcb_temp}°C, bat: temp {battery_temp}, {battery_voltage}V",
+            "tp: {calipile_object} obj / {calipile_ambient} amb, accel: {accel}, gyro: {gyro}, steer: {steer}, orient: {orientation}, pcb: {pcb_temp}, bat: temp {battery_temp}, {battery_voltage}",
         )
     }
 }
@@ -0,0 +1,346 @@
+//! Conversion of raw central station and ant telemetry to engineering units.
+//!
+//! Each convertible field has an optional [`Conversion`]. Fields without one are shown raw, with
+//! no unit. The calibration in effect for the process is [`install`]ed once at startup (typically
+//! loaded from JSON) and is what the payloads' `display()` uses.
+
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+    },
+    fs,
+    io,
+    path::Path,
+};
+
+use once_cell::sync::OnceCell;
+
+use crate::{
+    ant,
+    cs,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Format(#[from] serde_json::Error),
+
+    #[error("calibration for {field}: {reason}")]
+    Invalid {
+        field:  &'static str,
+        reason: &'static str,
+    },
+}
+
+/// Maps a raw reading to engineering units.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Conversion {
+    /// `c[0] + c[1] x + c[2] x^2 + ...`
+    Polynomial {
+        coefficients: Vec<f64>,
+    },
+
+    /// Linear interpolation between `(raw, value)` points, in increasing order of `raw`. Readings
+    /// outside the table are clamped to its ends.
+    Table {
+        points: Vec<(f64, f64)>,
+    },
+}
+
+impl Conversion {
+    pub fn apply(&self, raw: f64) -> f64 {
+        match self {
+            Conversion::Polynomial {
+                coefficients,
+            } => coefficients.iter().rev().fold(0., |acc, c| acc * raw + c),
+
+            Conversion::Table {
+                points,
+            } => {
+                let i = points.partition_point(|&(x, _)| x <= raw);
+
+                match (i.checked_sub(1).map(|i| points[i]), points.get(i)) {
+                    (Some((x0, y0)), Some(&(x1, y1))) => y0 + (y1 - y0) * (raw - x0) / (x1 - x0),
+                    (Some((_, y)), None) | (None, Some(&(_, y))) => y,
+                    (None, None) => raw,
+                }
+            },
+        }
+    }
+
+    fn validate(&self) -> Result<(), &'static str> {
+        match self {
+            Conversion::Polynomial {
+                coefficients,
+            } => {
+                if coefficients.is_empty() {
+                    return Err("polynomial has no coefficients");
+                }
+
+                if !coefficients.iter().all(|c| c.is_finite()) {
+                    return Err("polynomial coefficients must be finite");
+                }
+            },
+
+            Conversion::Table {
+                points,
+            } => {
+                if points.len() < 2 {
+                    return Err("table needs at least two points");
+                }
+
+                if !points.iter().all(|(x, y)| x.is_finite() && y.is_finite()) {
+                    return Err("table points must be finite");
+                }
+
+                if !points.windows(2).all(|w| w[0].0 < w[1].0) {
+                    return Err("table points must be in strictly increasing order of raw value");
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Engineering unit of a converted reading.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Celsius,
+    Volts,
+}
+
+impl Display for Unit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unit::Celsius => f.write_str("°C"),
+            Unit::Volts => f.write_str("V"),
+        }
+    }
+}
+
+/// A telemetry field, converted if there's a calibration for it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Reading {
+    Raw(u32),
+    Converted {
+        value: f64,
+        unit:  Unit,
+    },
+}
+
+impl Reading {
+    /// The value in engineering units, if the field is calibrated.
+    #[inline]
+    pub fn value(&self) -> Option<f64> {
+        match *self {
+            Reading::Raw(_) => None,
+            Reading::Converted {
+                value,
+                ..
+            } => Some(value),
+        }
+    }
+}
+
+impl Display for Reading {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reading::Raw(raw) => write!(f, "{raw}"),
+            Reading::Converted {
+                value,
+                unit,
+            } => write!(f, "{value:.2}{unit}"),
+        }
+    }
+}
+
+/// Conversions for each calibrated telemetry field. All fields are raw by default.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Calibration {
+    pub cs_temperature: Option<Conversion>,
+    pub power_5v:       Option<Conversion>,
+    pub power_vcc:      Option<Conversion>,
+
+    pub pcb_temp:         Option<Conversion>,
+    pub battery_temp:     Option<Conversion>,
+    pub battery_voltage:  Option<Conversion>,
+    pub calipile_object:  Option<Conversion>,
+    pub calipile_ambient: Option<Conversion>,
+}
+
+#[inline]
+fn convert(conversion: &Option<Conversion>, raw: u32, unit: Unit) -> Reading {
+    match conversion {
+        Some(conversion) => Reading::Converted {
+            value: conversion.apply(raw as f64),
+            unit,
+        },
+        None => Reading::Raw(raw),
+    }
+}
+
+impl Calibration {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let data = fs::read(path)?;
+        let calibration: Calibration = serde_json::from_slice(&data)?;
+
+        calibration.validate()?;
+
+        Ok(calibration)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let fields = [
+            ("cs_temperature", &self.cs_temperature),
+            ("power_5v", &self.power_5v),
+            ("power_vcc", &self.power_vcc),
+            ("pcb_temp", &self.pcb_temp),
+            ("battery_temp", &self.battery_temp),
+            ("battery_voltage", &self.battery_voltage),
+            ("calipile_object", &self.calipile_object),
+            ("calipile_ambient", &self.calipile_ambient),
+        ];
+
+        for (field, conversion) in fields {
+            if let Some(Err(reason)) = conversion.as_ref().map(Conversion::validate) {
+                return Err(Error::Invalid {
+                    field,
+                    reason,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn cs_temperature(&self, payload: &cs::Payload) -> Reading {
+        convert(&self.cs_temperature, payload.temperature as u32, Unit::Celsius)
+    }
+
+    #[inline]
+    pub fn power_5v(&self, payload: &cs::Payload) -> Reading {
+        convert(&self.power_5v, payload.power_5v as u32, Unit::Volts)
+    }
+
+    #[inline]
+    pub fn power_vcc(&self, payload: &cs::Payload) -> Reading {
+        convert(&self.power_vcc, payload.power_vcc as u32, Unit::Volts)
+    }
+
+    #[inline]
+    pub fn pcb_temp(&self, payload: &ant::Payload) -> Reading {
+        convert(&self.pcb_temp, payload.pcb_temp as u32, Unit::Celsius)
+    }
+
+    #[inline]
+    pub fn battery_temp(&self, payload: &ant::Payload) -> Reading {
+        convert(&self.battery_temp, payload.battery_temp as u32, Unit::Celsius)
+    }
+
+    #[inline]
+    pub fn battery_voltage(&self, payload: &ant::Payload) -> Reading {
+        convert(&self.battery_voltage, payload.battery_voltage as u32, Unit::Volts)
+    }
+
+    #[inline]
+    pub fn calipile_object(&self, payload: &ant::Payload) -> Reading {
+        convert(&self.calipile_object, payload.calipile_object, Unit::Celsius)
+    }
+
+    #[inline]
+    pub fn calipile_ambient(&self, payload: &ant::Payload) -> Reading {
+        convert(&self.calipile_ambient, payload.calipile_ambient, Unit::Celsius)
+    }
+}
+
+static CURRENT: OnceCell<Calibration> = OnceCell::new();
+
+/// Set the calibration used for the rest of the process. Fails (returning `calibration`) if one
+/// has already been installed or used.
+pub fn install(calibration: Calibration) -> Result<(), Calibration> {
+    CURRENT.set(calibration)
+}
+
+/// The installed calibration, or the default (everything raw) if none was installed.
+#[inline]
+pub fn current() -> &'static Calibration {
+    CURRENT.get_or_init(Calibration::default)
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn table() -> Conversion {
+        Conversion::Table {
+            points: vec![(0., -40.), (100., 10.), (200., 110.)],
+        }
+    }
+
+    #[test]
+    fn polynomial() {
+        let conversion = Conversion::Polynomial {
+            coefficients: vec![-50., 0.5, 0.01],
+        };
+
+        assert_eq!(conversion.apply(0.), -50.);
+        assert_eq!(conversion.apply(10.), -44.);
+        assert!(conversion.validate().is_ok());
+    }
+
+    #[test]
+    fn table_interpolates() {
+        let table = table();
+
+        assert_eq!(table.apply(0.), -40.);
+        assert_eq!(table.apply(50.), -15.);
+        assert_eq!(table.apply(100.), 10.);
+        assert_eq!(table.apply(150.), 60.);
+
+        // clamped outside the table
+        assert_eq!(table.apply(-10.), -40.);
+        assert_eq!(table.apply(1000.), 110.);
+    }
+
+    #[test]
+    fn rejects_bad_conversions() {
+        let unsorted = Conversion::Table {
+            points: vec![(10., 0.), (5., 1.)],
+        };
+
+        assert!(unsorted.validate().is_err());
+        assert!(
+            Conversion::Table {
+                points: vec![(0., 0.)],
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            Conversion::Polynomial {
+                coefficients: vec![],
+            }
+            .validate()
+            .is_err()
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn table_stays_in_range(raw in any::<u32>()) {
+            let value = table().apply(raw as f64);
+
+            assert!((-40. ..=110.).contains(&value));
+        }
+    }
+}
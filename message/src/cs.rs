@@ -1,6 +1,9 @@
 use packed_struct::prelude::*;
 
-use crate::Vec3;
+use crate::{
+    calibration,
+    Vec3,
+};
 
 #[derive(
     Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, Hash, PackedStruct,
@@ -26,9 +29,6 @@ impl Payload {
     #[inline]
     pub fn display(&self) -> String {
         let &Payload {
-            temperature,
-            power_5v,
-            power_vcc,
             fram_used,
             accel,
             heat_enabled,
@@ -43,8 +43,14 @@ impl Payload {
 
         let accel = accel.display();
 
+        let cal = calibration::current();
+
+        let temperature = cal.cs_temperature(self);
+        let power_5v = cal.power_5v(self);
+        let power_vcc = cal.power_vcc(self);
+
         format!(
-            "temp: {temperature}, {power_5v}v/5v, {power_vcc}/3v, accel: {accel}, heat: {heat}, fram: {fram_used}B"
+            "temp: {temperature}, 5v: {power_5v}, vcc: {power_vcc}, accel: {accel}, heat: {heat}, fram: {fram_used}B"
         )
     }
 }
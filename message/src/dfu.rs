@@ -0,0 +1,246 @@
+//! Firmware update payloads.
+//!
+//! The ground uplinks an image to the frontend in chunks (`Event::FEAntDFU`), and the frontend
+//! streams it on to the central station (`Event::CSAntDFUInit`, `Event::CSAntDFUPacket`). Both legs
+//! describe the image with [`ImageInfo`] and prefix image data with a [`ChunkHeader`].
+//!
+//! Central station firmware is uplinked the same way (`Event::FECSDFU`), but sent straight to the
+//! central station's bootloader, framed with a [`BootHeader`] and acked per frame with a
+//! [`BootResponse`].
+
+use bytes::Bytes;
+use packed_struct::{
+    prelude::*,
+    PackedStructSlice,
+};
+
+const CRC: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+/// End-to-end checksum over a complete firmware image.
+#[inline]
+pub fn checksum(image: &[u8]) -> u32 {
+    CRC.checksum(image)
+}
+
+/// Size and checksum of a complete image. Payload of `CSAntDFUInit`.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, PackedStruct,
+)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "8", endian = "lsb")]
+pub struct ImageInfo {
+    pub size:     u32,
+    pub checksum: u32,
+}
+
+/// Precedes a chunk of image data. Payload prefix of `CSAntDFUPacket`.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, PackedStruct,
+)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "4", endian = "lsb")]
+pub struct ChunkHeader {
+    /// Byte offset of the chunk within the image.
+    pub offset: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
+pub enum Op {
+    Begin = 0x01,
+    Chunk = 0x02,
+    Flash = 0x03,
+    Abort = 0x04,
+}
+
+/// A firmware update command from the ground. Encoded as a one-byte [`Op`] followed by the
+/// op-specific fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Command {
+    /// Discard any previous image and start receiving a new one.
+    Begin(ImageInfo),
+
+    /// A piece of the image.
+    Chunk {
+        offset: u32,
+        data:   Bytes,
+    },
+
+    /// Stream the verified image to its target. Resumes from the last acked packet unless
+    /// `from_start` is set (bootloader transfers always start over).
+    Flash {
+        from_start: bool,
+    },
+
+    /// Stop streaming and discard the image.
+    Abort,
+}
+
+impl Command {
+    pub fn pack_to_vec(&self) -> PackingResult<Vec<u8>> {
+        let mut out = vec![];
+
+        let op = match self {
+            Command::Begin(_) => Op::Begin,
+            Command::Chunk {
+                ..
+            } => Op::Chunk,
+            Command::Flash {
+                ..
+            } => Op::Flash,
+            Command::Abort => Op::Abort,
+        };
+
+        out.push(op.to_primitive());
+
+        match self {
+            Command::Begin(info) => out.extend_from_slice(&info.pack()?),
+            Command::Chunk {
+                offset,
+                data,
+            } => {
+                out.extend_from_slice(
+                    &ChunkHeader {
+                        offset: *offset,
+                    }
+                    .pack()?,
+                );
+                out.extend_from_slice(data);
+            },
+            Command::Flash {
+                from_start,
+            } => out.push(*from_start as u8),
+            Command::Abort => {},
+        }
+
+        Ok(out)
+    }
+
+    pub fn unpack_from_slice(src: &[u8]) -> PackingResult<Self> {
+        let (&op, rest) = src.split_first().ok_or(PackingError::BufferTooSmall)?;
+        let op = Op::from_primitive(op).ok_or(PackingError::InvalidValue)?;
+
+        let fixed = |len: usize| {
+            if rest.len() != len {
+                return Err(PackingError::BufferSizeMismatch {
+                    expected: len,
+                    actual:   rest.len(),
+                });
+            }
+
+            Ok(rest)
+        };
+
+        Ok(match op {
+            Op::Begin => Command::Begin(ImageInfo::unpack_from_slice(fixed(8)?)?),
+            Op::Chunk => {
+                if rest.len() < 4 {
+                    return Err(PackingError::BufferTooSmall);
+                }
+
+                let (hdr, data) = rest.split_at(4);
+
+                Command::Chunk {
+                    offset: ChunkHeader::unpack_from_slice(hdr)?.offset,
+                    data:   Bytes::copy_from_slice(data),
+                }
+            },
+            Op::Flash => Command::Flash {
+                from_start: fixed(1)?[0] != 0,
+            },
+            Op::Abort => {
+                fixed(0)?;
+                Command::Abort
+            },
+        })
+    }
+}
+
+/// Kind of a frame sent to the central station bootloader.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
+pub enum BootOp {
+    /// Start a transfer. Followed by an [`ImageInfo`].
+    Start  = 0x01,
+    /// Followed by a [`ChunkHeader`] and the block data.
+    Block  = 0x02,
+    /// All blocks sent: the bootloader checks the image against the [`ImageInfo`] it was started
+    /// with and boots it if it matches.
+    Finish = 0x03,
+    /// Discard the transfer and boot the existing firmware.
+    Abort  = 0x04,
+}
+
+/// Precedes every frame sent to the central station bootloader.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PackedStruct)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "3", endian = "lsb")]
+pub struct BootHeader {
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub op:  BootOp,
+    /// Echoed by the bootloader in its response.
+    pub seq: u16,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
+pub enum BootStatus {
+    Ack = 0x06,
+    Nak = 0x15,
+}
+
+/// The bootloader's response to each frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PackedStruct)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "3", endian = "lsb")]
+pub struct BootResponse {
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub status: BootStatus,
+    pub seq:    u16,
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn pack_unpack_equivalence(cmd in command_strategy()) {
+            let packed = cmd.pack_to_vec().unwrap();
+            let unpacked = Command::unpack_from_slice(&packed).unwrap();
+
+            assert_eq!(cmd, unpacked);
+        }
+
+        #[test]
+        fn unpack_arbitrary(data in prop::collection::vec(any::<u8>(), 0..64)) {
+            if let Ok(cmd) = Command::unpack_from_slice(&data) {
+                let packed = cmd.pack_to_vec().unwrap();
+                assert_eq!(Command::unpack_from_slice(&packed).unwrap(), cmd);
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn boot_response_unpack_pack_equivalence(data in any::<[u8; 3]>()) {
+            if let Ok(resp) = BootResponse::unpack(&data) {
+                assert_eq!(Ok(data), resp.pack());
+            }
+        }
+    }
+
+    fn command_strategy() -> impl Strategy<Value = Command> {
+        prop_oneof![
+            (any::<u32>(), any::<u32>()).prop_map(|(size, checksum)| Command::Begin(ImageInfo {
+                size,
+                checksum
+            })),
+            (any::<u32>(), prop::collection::vec(any::<u8>(), 0..256)).prop_map(
+                |(offset, data)| Command::Chunk {
+                    offset,
+                    data: Bytes::from(data),
+                }
+            ),
+            any::<bool>().prop_map(|from_start| Command::Flash {
+                from_start
+            }),
+            Just(Command::Abort),
+        ]
+    }
+}
@@ -0,0 +1,73 @@
+use std::fmt::{
+    Display,
+    Formatter,
+};
+
+use crate::{
+    ant,
+    downlink::state_machine::State,
+    header::Event,
+};
+
+/// Acknowledgement of an ant operation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Ack {
+    /// Acked by the central station on the ant's behalf.
+    CentralStation,
+
+    /// Acked by the ant itself, with its telemetry at the time.
+    Ant(ant::Payload),
+}
+
+impl Display for Ack {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ack::CentralStation => write!(f, "central station"),
+            Ack::Ant(payload) => write!(f, "ant ({})", payload.display()),
+        }
+    }
+}
+
+/// Outcome of a ground-initiated ant operation (calibration, heater, test commands).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    /// The operation isn't allowed in the current state and was not sent.
+    Rejected {
+        event: Event,
+        state: State,
+    },
+
+    /// The operation is already in progress.
+    Busy(Event),
+
+    Acked {
+        event: Event,
+        ack:   Ack,
+    },
+
+    /// The operation was not acked after all retries.
+    Failed {
+        event: Event,
+        error: String,
+    },
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Rejected {
+                event,
+                state,
+            } => write!(f, "{event:?} rejected: not allowed in {state}"),
+            Report::Busy(event) => write!(f, "{event:?} rejected: already in progress"),
+            Report::Acked {
+                event,
+                ack,
+            } => write!(f, "{event:?} acked by {ack}"),
+            Report::Failed {
+                event,
+                error,
+            } => write!(f, "{event:?} failed: {error}"),
+        }
+    }
+}
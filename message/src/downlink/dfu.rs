@@ -0,0 +1,99 @@
+use std::fmt::{
+    Display,
+    Formatter,
+};
+
+/// Progress of a firmware update.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    /// Image bytes received from the ground so far.
+    Receiving {
+        received: u32,
+        size:     u32,
+    },
+
+    /// The complete image was received and matches its checksum.
+    Verified {
+        size:     u32,
+        checksum: u32,
+    },
+
+    /// A command from the ground could not be applied.
+    Rejected(String),
+
+    /// Image bytes acked by the target so far.
+    Streaming {
+        sent: u32,
+        size: u32,
+    },
+
+    /// The ant image is on the central station, which has been told to install it on the ant.
+    Applying,
+
+    /// The central station couldn't be made to install the streamed ant image. Flashing again
+    /// retries without streaming the image again.
+    ApplyFailed(String),
+
+    /// The target is in its bootloader: normal serial relaying is suspended until the transfer
+    /// ends.
+    Bootloader,
+
+    /// Streaming stopped after exhausting retries. Flashing again resumes from `sent`.
+    Stalled {
+        sent:  u32,
+        size:  u32,
+        error: String,
+    },
+
+    /// A bootloader transfer failed at `sent` and normal operation was restored.
+    Failed {
+        sent:  u32,
+        size:  u32,
+        error: String,
+    },
+
+    Complete {
+        size:     u32,
+        checksum: u32,
+    },
+
+    Aborted,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Receiving {
+                received,
+                size,
+            } => write!(f, "received {received}/{size}B"),
+            Report::Verified {
+                size,
+                checksum,
+            } => write!(f, "verified {size}B image (crc {checksum:#010x})"),
+            Report::Rejected(reason) => write!(f, "rejected: {reason}"),
+            Report::Streaming {
+                sent,
+                size,
+            } => write!(f, "sent {sent}/{size}B"),
+            Report::Stalled {
+                sent,
+                size,
+                error,
+            } => write!(f, "stalled at {sent}/{size}B: {error}"),
+            Report::Applying => write!(f, "image streamed, applying"),
+            Report::ApplyFailed(error) => write!(f, "applying image failed: {error}"),
+            Report::Bootloader => write!(f, "in bootloader, relaying suspended"),
+            Report::Failed {
+                sent,
+                size,
+                error,
+            } => write!(f, "failed at {sent}/{size}B, relaying restored: {error}"),
+            Report::Complete {
+                size,
+                checksum,
+            } => write!(f, "complete: {size}B image (crc {checksum:#010x})"),
+            Report::Aborted => write!(f, "aborted"),
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+    },
+    time::Duration,
+};
+
+use crate::UniqueId;
+
+/// Statistics for a dump of ant data relayed after a move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Stats {
+    /// The AntStart that triggered the dump.
+    pub trigger:      UniqueId,
+    /// Distinct packets received.
+    pub packets:      u32,
+    pub duplicates:   u32,
+    /// Packets skipped in the ant's sequence numbering and never received.
+    pub missing:      u32,
+    /// Packets that arrived after a later one.
+    pub out_of_order: u32,
+    /// Time between the earliest and latest packet timestamps.
+    pub duration:     Duration,
+    /// Drop in the ant's FRAM usage over the dump.
+    pub fram_drained: u32,
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let Stats {
+            trigger,
+            packets,
+            duplicates,
+            missing,
+            out_of_order,
+            duration,
+            fram_drained,
+        } = self;
+
+        write!(
+            f,
+            "dump for {trigger}: {packets} packets over {duration:?}, {fram_drained}B fram drained \
+             ({missing} missing, {duplicates} duplicates, {out_of_order} out of order)"
+        )
+    }
+}
+
+/// Ant data dumps.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    /// The first packet of a dump arrived.
+    Started {
+        trigger: UniqueId,
+    },
+
+    /// No more packets have arrived for the dump.
+    Complete(Stats),
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Started {
+                trigger,
+            } => write!(f, "dump started for {trigger}"),
+            Report::Complete(stats) => write!(f, "complete: {stats}"),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::{
+    Display,
+    Formatter,
+};
+
+use crate::header::{
+    Destination,
+    Event,
+};
+
+/// A telemetry field subject to limit checking. Ant fields come from the ant payload of relayed
+/// `CSRelay` messages, central station fields from their header.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+pub enum Field {
+    #[serde(rename = "pcb_temp")]
+    PcbTemp,
+    #[serde(rename = "battery_temp")]
+    BatteryTemp,
+    #[serde(rename = "battery_voltage")]
+    BatteryVoltage,
+    #[serde(rename = "fram_usage")]
+    FramUsage,
+
+    #[serde(rename = "cs_temperature")]
+    CsTemperature,
+    #[serde(rename = "power_5v")]
+    Power5v,
+    #[serde(rename = "power_vcc")]
+    PowerVcc,
+    #[serde(rename = "fram_used")]
+    FramUsed,
+}
+
+impl Field {
+    /// Name of the field, as used in configuration.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Field::PcbTemp => "pcb_temp",
+            Field::BatteryTemp => "battery_temp",
+            Field::BatteryVoltage => "battery_voltage",
+            Field::FramUsage => "fram_usage",
+            Field::CsTemperature => "cs_temperature",
+            Field::Power5v => "power_5v",
+            Field::PowerVcc => "power_vcc",
+            Field::FramUsed => "fram_used",
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How far out of limits a field is.
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum Level {
+    #[default]
+    Nominal,
+    Yellow,
+    Red,
+}
+
+impl Display for Level {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Level::Nominal => f.write_str("nominal"),
+            Level::Yellow => f.write_str("yellow"),
+            Level::Red => f.write_str("red"),
+        }
+    }
+}
+
+/// Telemetry limit violations and the responses they triggered.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    /// `field` moved from `from` to `to` on reading `value`.
+    Changed {
+        field: Field,
+        from:  Level,
+        to:    Level,
+        value: u32,
+    },
+
+    /// A command sent in response to `field` reaching `level` wasn't acked.
+    ResponseFailed {
+        field:       Field,
+        level:       Level,
+        destination: Destination,
+        event:       Event,
+        error:       String,
+    },
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Changed {
+                field,
+                from,
+                to,
+                value,
+            } => write!(f, "{field} {from} -> {to} (value {value})"),
+            Report::ResponseFailed {
+                field,
+                level,
+                destination,
+                event,
+                error,
+            } => write!(f, "{field} {level} response {event:?} -> {destination:?} failed: {error}"),
+        }
+    }
+}
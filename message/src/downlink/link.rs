@@ -0,0 +1,160 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+    },
+    time::Duration,
+};
+
+use crate::{
+    header::Event,
+    UniqueId,
+};
+
+/// Health of the links to the central station and the rover.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    /// The central station has missed `missed` consecutive pings.
+    CsUnresponsive {
+        missed:       u32,
+        reconnecting: bool,
+    },
+
+    /// The central station is still unresponsive: the rover should power-cycle it.
+    CsPowerCycleRequested {
+        missed: u32,
+    },
+
+    /// The central station acked a ping after having been declared unresponsive.
+    CsRecovered {
+        missed:   u32,
+        down_for: Duration,
+    },
+
+    /// Nothing has been received from the rover for `silent_for`: the ant has been safed.
+    RoverSilent {
+        silent_for: Duration,
+    },
+
+    /// The rover is uplinking again after having gone silent.
+    RoverRecovered {
+        down_for: Duration,
+    },
+
+    /// An ack echoed a different checksum from the command it answers, suggesting corruption or a
+    /// sequence number/timestamp collision. The ack was rejected.
+    AckChecksumMismatch {
+        event:      Event,
+        expected:   u8,
+        actual:     u8,
+        /// Mismatches seen since the relay started.
+        mismatches: u32,
+    },
+
+    /// A command's id collided with an in-flight request, so it was re-stamped before being sent to
+    /// the central station.
+    IdCollision {
+        event:     Event,
+        original:  UniqueId,
+        restamped: UniqueId,
+    },
+
+    /// The serial connection to the central station dropped or couldn't be opened. Starts outage
+    /// number `outages`.
+    SerialLost {
+        outages: u32,
+    },
+
+    /// The serial connection is back after `down_for`.
+    SerialRestored {
+        down_for: Duration,
+        outages:  u32,
+    },
+
+    /// Serial link state, in answer to `FEStatus`.
+    SerialStatus {
+        connected: bool,
+        /// Outages since the relay started.
+        outages:   u32,
+        /// Total time spent in completed outages.
+        down_for:  Duration,
+        /// Length of the ongoing outage, if any.
+        current:   Option<Duration>,
+    },
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::CsUnresponsive {
+                missed,
+                reconnecting,
+            } => {
+                write!(f, "central station unresponsive: {missed} pings missed")?;
+
+                if *reconnecting {
+                    write!(f, ", reconnecting serial")?;
+                }
+
+                Ok(())
+            },
+            Report::CsPowerCycleRequested {
+                missed,
+            } => write!(f, "central station power cycle requested: {missed} pings missed"),
+            Report::CsRecovered {
+                missed,
+                down_for,
+            } => write!(f, "central station recovered after {missed} missed pings ({down_for:?})"),
+            Report::RoverSilent {
+                silent_for,
+            } => write!(f, "no uplink from rover for {silent_for:?}, safing ant"),
+            Report::RoverRecovered {
+                down_for,
+            } => write!(f, "rover uplink recovered after {down_for:?}"),
+            Report::AckChecksumMismatch {
+                event,
+                expected,
+                actual,
+                mismatches,
+            } => write!(
+                f,
+                "rejected {event:?} ack echoing checksum {actual:#04x}, expected {expected:#04x} \
+                 ({mismatches} mismatches)"
+            ),
+            Report::IdCollision {
+                event,
+                original,
+                restamped,
+            } => write!(
+                f,
+                "{event:?} id {original} collided with an in-flight request, re-stamped as {restamped}"
+            ),
+            Report::SerialLost {
+                outages,
+            } => write!(f, "serial link lost (outage {outages})"),
+            Report::SerialRestored {
+                down_for,
+                outages,
+            } => write!(f, "serial link restored after {down_for:?} (outage {outages})"),
+            Report::SerialStatus {
+                connected,
+                outages,
+                down_for,
+                current,
+            } => {
+                let state = if *connected {
+                    "up"
+                } else {
+                    "down"
+                };
+                write!(f, "serial link {state}: {outages} outages totalling {down_for:?}")?;
+
+                if let Some(current) = current {
+                    write!(f, ", down for {current:?}")?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
@@ -5,11 +5,26 @@ use std::fmt::{
 };
 
 use crate::{
+    payload::{
+        self,
+        RealtimeStatus,
+    },
     BytesWrap,
     Message,
 };
 
+pub mod ant_op;
+pub mod dfu;
+pub mod dump;
+pub mod limits;
+pub mod link;
 pub mod log;
+pub mod queue;
+pub mod schedule;
+pub mod sequence;
+pub mod state_machine;
+pub mod telemetry;
+pub mod thermostat;
 mod value;
 
 pub use value::Value;
@@ -27,6 +42,21 @@ pub enum Downlink {
 
     SerialUplinkRaw(BytesWrap),
     SerialDownlinkRaw(BytesWrap),
+
+    StateMachine(state_machine::Report),
+    AntDfu(dfu::Report),
+    CsDfu(dfu::Report),
+    Link(link::Report),
+    AntOp(ant_op::Report),
+    Schedule(schedule::Report),
+    Sequence(sequence::Report),
+    Limits(limits::Report),
+    Thermostat(thermostat::Report),
+    Dump(dump::Report),
+    Telemetry(telemetry::Telemetry),
+    Status(RealtimeStatus),
+    Lifecycle(payload::log::Packed),
+    Queue(queue::Report),
 }
 
 impl Display for Downlink {
@@ -44,6 +74,21 @@ impl Display for Downlink {
 
             SerialUplinkRaw(b) => write!(f, "raw serial up: {b}"),
             SerialDownlinkRaw(b) => write!(f, "raw serial down: {b}"),
+
+            StateMachine(r) => write!(f, "state machine: {r}"),
+            AntDfu(r) => write!(f, "ant dfu: {r}"),
+            CsDfu(r) => write!(f, "cs dfu: {r}"),
+            Link(r) => write!(f, "link: {r}"),
+            AntOp(r) => write!(f, "ant op: {r}"),
+            Schedule(r) => write!(f, "schedule: {r}"),
+            Sequence(r) => write!(f, "sequence: {r}"),
+            Limits(r) => write!(f, "limits: {r}"),
+            Thermostat(r) => write!(f, "thermostat: {r}"),
+            Dump(r) => write!(f, "dump: {r}"),
+            Telemetry(t) => write!(f, "telemetry: {t}"),
+            Status(s) => write!(f, "status: {s}"),
+            Lifecycle(h) => write!(f, "lifecycle: {h}"),
+            Queue(r) => write!(f, "queue: {r}"),
         }
     }
 }
@@ -63,6 +108,21 @@ impl Debug for Downlink {
 
             SerialUplinkRaw(b) => write!(f, "SerialUplinkRaw({b})"),
             SerialDownlinkRaw(b) => write!(f, "SerialDownlinkRaw({b})"),
+
+            StateMachine(r) => write!(f, "StateMachine({r:?})"),
+            AntDfu(r) => write!(f, "AntDfu({r:?})"),
+            CsDfu(r) => write!(f, "CsDfu({r:?})"),
+            Link(r) => write!(f, "Link({r:?})"),
+            AntOp(r) => write!(f, "AntOp({r:?})"),
+            Schedule(r) => write!(f, "Schedule({r:?})"),
+            Sequence(r) => write!(f, "Sequence({r:?})"),
+            Limits(r) => write!(f, "Limits({r:?})"),
+            Thermostat(r) => write!(f, "Thermostat({r:?})"),
+            Dump(r) => write!(f, "Dump({r:?})"),
+            Telemetry(t) => write!(f, "Telemetry({t:?})"),
+            Status(s) => write!(f, "Status({s:?})"),
+            Lifecycle(h) => write!(f, "Lifecycle({h:?})"),
+            Queue(r) => write!(f, "Queue({r:?})"),
         }
     }
 }
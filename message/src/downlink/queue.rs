@@ -0,0 +1,99 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+    },
+    time::Duration,
+};
+
+/// Scheduling class of a command bound for the serial port. Higher classes are always sent first.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// Commands that make the ant or rover safe, e.g. `AntStop`.
+    Safety,
+    Control,
+    /// Firmware update traffic.
+    Bulk,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Safety, Priority::Control, Priority::Bulk];
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::Safety => f.write_str("safety"),
+            Priority::Control => f.write_str("control"),
+            Priority::Bulk => f.write_str("bulk"),
+        }
+    }
+}
+
+/// Serial queue activity for one priority class over a reporting interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct ClassStats {
+    pub priority:     Priority,
+    /// Commands waiting at the time of the report.
+    pub depth:        u32,
+    pub sent:         u32,
+    /// Queued commands dropped to make room for newer ones.
+    pub dropped:      u32,
+    /// Commands turned away because the queue was full.
+    pub rejected:     u32,
+    /// Time from being queued to being written to the serial port.
+    pub mean_latency: Duration,
+    pub max_latency:  Duration,
+}
+
+impl Display for ClassStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ClassStats {
+            priority,
+            depth,
+            sent,
+            dropped,
+            rejected,
+            mean_latency,
+            max_latency,
+        } = self;
+
+        write!(
+            f,
+            "{priority}: {depth} queued, {sent} sent (latency mean {mean_latency:?}, max \
+             {max_latency:?}), {dropped} dropped, {rejected} rejected"
+        )
+    }
+}
+
+/// Serial uplink scheduling.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    Stats {
+        /// Serial bandwidth budget, in bytes per second.
+        budget:  u32,
+        classes: Vec<ClassStats>,
+    },
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Stats {
+                budget,
+                classes,
+            } => {
+                write!(f, "serial queues (budget {budget}B/s)")?;
+
+                for class in classes {
+                    write!(f, "\n\t\t{class}")?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
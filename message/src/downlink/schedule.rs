@@ -0,0 +1,84 @@
+use std::fmt::{
+    Display,
+    Formatter,
+};
+
+use tap::Conv;
+
+use crate::{
+    header::{
+        Destination,
+        Event,
+    },
+    MissionEpoch,
+};
+
+/// A pending scheduled command.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    pub id:          u32,
+    pub at:          MissionEpoch,
+    pub destination: Destination,
+    pub event:       Event,
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ts = self.at.conv::<chrono::DateTime<chrono::Utc>>().format("%y/%m/%d %TZ");
+
+        write!(f, "#{} at {ts}: {:?} -> {:?}", self.id, self.event, self.destination)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    Scheduled(Entry),
+    Rejected(String),
+    Pending(Vec<Entry>),
+    Cancelled(Entry),
+    Cleared {
+        count: usize,
+    },
+
+    /// A scheduled command was sent and acked.
+    Acked(Entry),
+
+    /// A scheduled command was sent but never acked.
+    Failed {
+        entry: Entry,
+        error: String,
+    },
+
+    /// A scheduled ant op came due and was handed to the state machine, which reports its outcome
+    /// like any other ant op.
+    Dispatched(Entry),
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Scheduled(entry) => write!(f, "scheduled {entry}"),
+            Report::Rejected(reason) => write!(f, "rejected: {reason}"),
+            Report::Pending(entries) if entries.is_empty() => write!(f, "nothing pending"),
+            Report::Pending(entries) => {
+                write!(f, "{} pending", entries.len())?;
+
+                for entry in entries {
+                    write!(f, "\n\t\t{entry}")?;
+                }
+
+                Ok(())
+            },
+            Report::Cancelled(entry) => write!(f, "cancelled {entry}"),
+            Report::Cleared {
+                count,
+            } => write!(f, "cleared {count} pending"),
+            Report::Acked(entry) => write!(f, "{entry} acked"),
+            Report::Failed {
+                entry,
+                error,
+            } => write!(f, "{entry} failed: {error}"),
+            Report::Dispatched(entry) => write!(f, "{entry} dispatched as an ant op"),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt::{
+    Display,
+    Formatter,
+};
+
+use crate::sequence::Step;
+
+/// A stored sequence.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Summary {
+    pub name:  String,
+    pub steps: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    Defined(Summary),
+    Deleted(String),
+    Rejected(String),
+    Stored(Vec<Summary>),
+
+    Started(Summary),
+
+    /// Step `index` of the running sequence completed.
+    StepDone {
+        name:  String,
+        index: usize,
+        step:  Step,
+    },
+
+    Completed(String),
+
+    /// The running sequence was stopped at step `index`, either because the step failed or on
+    /// request from the ground.
+    Aborted {
+        name:   String,
+        index:  usize,
+        reason: String,
+    },
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Defined(Summary {
+                name,
+                steps,
+            }) => write!(f, "defined {name:?} ({steps} steps)"),
+            Report::Deleted(name) => write!(f, "deleted {name:?}"),
+            Report::Rejected(reason) => write!(f, "rejected: {reason}"),
+            Report::Stored(seqs) if seqs.is_empty() => write!(f, "none stored"),
+            Report::Stored(seqs) => {
+                write!(f, "{} stored", seqs.len())?;
+
+                for Summary {
+                    name,
+                    steps,
+                } in seqs
+                {
+                    write!(f, "\n\t\t{name:?}: {steps} steps")?;
+                }
+
+                Ok(())
+            },
+            Report::Started(Summary {
+                name,
+                steps,
+            }) => write!(f, "running {name:?} ({steps} steps)"),
+            Report::StepDone {
+                name,
+                index,
+                step,
+            } => write!(f, "{name:?} step {index} done: {step}"),
+            Report::Completed(name) => write!(f, "{name:?} completed"),
+            Report::Aborted {
+                name,
+                index,
+                reason,
+            } => write!(f, "{name:?} aborted at step {index}: {reason}"),
+        }
+    }
+}
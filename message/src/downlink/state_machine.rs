@@ -0,0 +1,176 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+    },
+    time::Duration,
+};
+
+use tap::Conv;
+
+use crate::{
+    header::Event,
+    MissionEpoch,
+};
+
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Hash,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    derive_more::Display,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[repr(u8)]
+pub enum State {
+    FlightIdle,
+    PingCentralStation,
+    GarageOpen,
+    BLEConnected,
+    AntReady,
+    AntRun,
+    /// The ant has been told to stop and hasn't acked yet.
+    AntStopping,
+}
+
+impl State {
+    pub const ALL: [State; 7] = [
+        State::FlightIdle,
+        State::PingCentralStation,
+        State::GarageOpen,
+        State::BLEConnected,
+        State::AntReady,
+        State::AntRun,
+        State::AntStopping,
+    ];
+    pub const INITIAL: State = State::FlightIdle;
+}
+
+/// A single recorded state machine transition.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Transition {
+    pub at:    MissionEpoch,
+    pub from:  State,
+    pub to:    State,
+    /// The event that caused the transition. `None` if it was caused by a timer or the completion
+    /// of a task.
+    pub event: Option<Event>,
+}
+
+impl Display for Transition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ts = self.at.conv::<chrono::DateTime<chrono::Utc>>().format("%y/%m/%d %TZ");
+
+        write!(f, "{ts}: {} -> {}", self.from, self.to)?;
+
+        match self.event {
+            Some(evt) => write!(f, " ({evt:?})"),
+            None => write!(f, " (deferred)"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Status {
+    pub state:         State,
+    pub pending_evt:   Option<Event>,
+    /// Descriptions of the running tasks.
+    pub running_tasks: Vec<String>,
+    pub time_in_state: Duration,
+    /// Most recent transitions, oldest first.
+    pub history:       Vec<Transition>,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} for {:?}", self.state, self.time_in_state)?;
+
+        if let Some(evt) = self.pending_evt {
+            write!(f, ", pending {evt:?}")?;
+        }
+
+        match self.running_tasks.as_slice() {
+            [] => write!(f, ", idle")?,
+            tasks => write!(f, ", running: [{}]", tasks.join(", "))?,
+        }
+
+        for t in &self.history {
+            write!(f, "\n\t\t{t}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A transition marked for reporting was taken.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Handled {
+    pub event:       Event,
+    pub from:        State,
+    pub to:          State,
+    pub pending_evt: Option<Event>,
+    /// Descriptions of the actions taken.
+    pub actions:     Vec<String>,
+}
+
+impl Display for Handled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: {} -> {}", self.event, self.from, self.to)?;
+
+        if let Some(evt) = self.pending_evt {
+            write!(f, ", pending {evt:?}")?;
+        }
+
+        write!(f, ", actions: [{}]", self.actions.join(", "))
+    }
+}
+
+/// A condition raised by the state machine that needs attention from the ground.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Alarm {
+    /// The ant never acked AntStop, so it may still be driving.
+    StopUnconfirmed,
+}
+
+impl Display for Alarm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Alarm::StopUnconfirmed => write!(f, "ant stop not confirmed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    Status(Status),
+    Handled(Handled),
+    /// A command sent with retries in the background completed.
+    Command {
+        event: Event,
+        acked: bool,
+    },
+    Alarm(Alarm),
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Status(status) => write!(f, "status: {status}"),
+            Report::Handled(handled) => write!(f, "handled {handled}"),
+            Report::Command {
+                event,
+                acked: true,
+            } => write!(f, "{event:?} acked"),
+            Report::Command {
+                event,
+                acked: false,
+            } => write!(f, "{event:?} failed"),
+            Report::Alarm(alarm) => write!(f, "ALARM: {alarm}"),
+        }
+    }
+}
@@ -0,0 +1,27 @@
+use std::fmt::{
+    Display,
+    Formatter,
+};
+
+use tap::Conv;
+
+use crate::{
+    CSRelay,
+    MissionEpoch,
+};
+
+/// Central station and ant telemetry decoded from a relayed ant message.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Telemetry {
+    /// When the relay received the message.
+    pub received: MissionEpoch,
+    pub relay:    CSRelay,
+}
+
+impl Display for Telemetry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ts = self.received.conv::<chrono::DateTime<chrono::Utc>>().format("%y/%m/%d %T%.3fZ");
+
+        write!(f, "received {ts}: {}", self.relay)
+    }
+}
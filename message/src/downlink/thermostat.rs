@@ -0,0 +1,54 @@
+use std::fmt::{
+    Display,
+    Formatter,
+};
+
+/// Closed-loop ant heater control.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Report {
+    Enabled,
+    Disabled,
+
+    /// The heater was commanded on or off on reading `temperature`, or off because the
+    /// thermostat was disabled if `temperature` is `None`.
+    Heater {
+        on:          bool,
+        temperature: Option<u32>,
+    },
+
+    /// A heater command wasn't acked, or wasn't allowed in the current state.
+    Failed {
+        on:    bool,
+        error: String,
+    },
+}
+
+#[inline]
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Report::Enabled => write!(f, "enabled"),
+            Report::Disabled => write!(f, "disabled"),
+            Report::Heater {
+                on,
+                temperature: Some(temperature),
+            } => write!(f, "heater {} at temperature {temperature}", on_off(*on)),
+            Report::Heater {
+                on,
+                temperature: None,
+            } => write!(f, "heater {} (thermostat disabled)", on_off(*on)),
+            Report::Failed {
+                on,
+                error,
+            } => write!(f, "heater {} command failed: {error}", on_off(*on)),
+        }
+    }
+}
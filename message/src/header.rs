@@ -197,9 +197,27 @@ pub enum Event {
     FEPing          = 0x25,
     FERestart       = 0x26,
     FECSPowerCycled = 0x27,
+    FEStatus        = 0x28,
+    FEAntDFU        = 0x29,
+    FECSDFU         = 0x2a,
+    // raised internally by the uplink heartbeat monitor:
+    FEUplinkLost    = 0x2b,
+    FEUplinkResumed = 0x2c,
+
+    FESchedule      = 0x2d,
+    FESequence      = 0x2e,
 
     #[cfg(debug_assertions)]
     DebugCSPing     = 0x2f,
+
+    // raised internally by the telemetry limits engine:
+    FELimitTrip     = 0x30,
+
+    FEThermostatOn  = 0x31,
+    FEThermostatOff = 0x32,
+
+    // raised internally when a dump of ant data goes quiet:
+    FEDumpComplete  = 0x33,
 }
 
 #[cfg(test)]
@@ -298,6 +316,17 @@ mod test {
             Just(Event::FERoverMove),
             Just(Event::FEPowerSupplied),
             Just(Event::FEPing),
+            Just(Event::FEStatus),
+            Just(Event::FEAntDFU),
+            Just(Event::FECSDFU),
+            Just(Event::FEUplinkLost),
+            Just(Event::FEUplinkResumed),
+            Just(Event::FESchedule),
+            Just(Event::FESequence),
+            Just(Event::FELimitTrip),
+            Just(Event::FEThermostatOn),
+            Just(Event::FEThermostatOff),
+            Just(Event::FEDumpComplete),
             #[cfg(debug_assertions)]
             Just(Event::DebugCSPing),
         ]
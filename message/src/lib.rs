@@ -13,15 +13,19 @@ use tap::Conv;
 
 pub mod ant;
 mod bytes_wrap;
+pub mod calibration;
 pub mod checksum;
 pub mod crc;
 pub mod cs;
+pub mod dfu;
 pub mod downlink;
 pub mod header;
 mod header_packet;
 mod magic_value;
 mod mission_epoch;
 pub mod payload;
+pub mod schedule;
+pub mod sequence;
 pub mod source_info;
 
 pub use bytes_wrap::BytesWrap;
@@ -50,12 +54,26 @@ pub type AntPacket<CRC = StandardCRC> =
     crc::WithCRC<HeaderPacket<HeaderWithSource, ant::Payload>, CRC>;
 pub type CSRelay = HeaderPacket<cs::Payload, AntPacket>;
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(
+    Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
 pub struct UniqueId {
     timestamp: MissionEpoch,
     seq:       u8,
 }
 
+impl UniqueId {
+    #[inline]
+    pub fn timestamp(&self) -> MissionEpoch {
+        self.timestamp
+    }
+
+    #[inline]
+    pub fn seq(&self) -> u8 {
+        self.seq
+    }
+}
+
 impl Display for UniqueId {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} [seq {}]", self.timestamp.conv::<chrono::DateTime<chrono::Utc>>(), self.seq)
@@ -79,6 +97,22 @@ pub fn command(env: &Params, dest: Destination, event: Event) -> Message<BytesWr
     })
 }
 
+#[inline]
+pub fn command_with_payload(
+    env: &Params,
+    dest: Destination,
+    event: Event,
+    payload: impl AsRef<[u8]>,
+) -> Message<BytesWrap, StandardCRC> {
+    Message::new(HeaderPacket {
+        header:  HeaderPacket {
+            header:  Header::command(env, dest, event),
+            payload: SourceInfo::Empty,
+        },
+        payload: BytesWrap::from(payload.as_ref()),
+    })
+}
+
 #[inline]
 pub fn downlink(env: &Params, b: impl AsRef<[u8]>) -> Message<BytesWrap, StandardCRC> {
     Message::new(HeaderPacket {
@@ -90,6 +124,20 @@ pub fn downlink(env: &Params, b: impl AsRef<[u8]>) -> Message<BytesWrap, Standar
     })
 }
 
+/// The relayed central station and ant telemetry carried by `msg`, if it's a `CSRelay` ack that
+/// unpacks cleanly.
+pub fn relay(msg: &Message) -> Option<CSRelay> {
+    if !matches!(msg.header.header.ty, header::MessageType {
+        event: Event::CSRelay,
+        disposition: header::Disposition::Ack,
+        ..
+    }) {
+        return None;
+    }
+
+    CSRelay::unpack_from_slice(msg.payload.as_ref()).ok()
+}
+
 #[derive(
     Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize, Hash, PackedStruct, Default,
 )]
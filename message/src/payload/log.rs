@@ -1,4 +1,10 @@
+use std::fmt::{
+    Display,
+    Formatter,
+};
+
 use packed_struct::prelude::*;
+use tap::Conv;
 
 use crate::{
     HeaderPacket,
@@ -7,7 +13,8 @@ use crate::{
 
 pub type Log = HeaderPacket<Header, Box<dyn PackedStructSlice>>;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, PackedStruct)]
+/// A relay lifecycle event.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PackedStruct, serde::Serialize, serde::Deserialize)]
 pub struct Header {
     #[packed_field(size_bytes = "4")]
     pub timestamp: MissionEpoch,
@@ -15,7 +22,9 @@ pub struct Header {
     pub ty:        Type,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8, serde::Serialize, serde::Deserialize,
+)]
 pub enum Type {
     Startup,
     DownlinkConnected,
@@ -26,3 +35,69 @@ pub enum Type {
     Interrupted,
     Shutdown,
 }
+
+impl Header {
+    #[inline]
+    pub fn now(ty: Type) -> Self {
+        Self {
+            timestamp: MissionEpoch::now(),
+            ty,
+        }
+    }
+}
+
+impl Display for Header {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let ts = self.timestamp.conv::<chrono::DateTime<chrono::Utc>>().format("%y/%m/%d %T%.3fZ");
+
+        write!(f, "{:?} at {ts}", self.ty)
+    }
+}
+
+/// A [`Header`] in its packed wire form, as downlinked: five bytes, rather than a serialized
+/// struct.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Packed(pub [u8; 5]);
+
+impl Packed {
+    #[inline]
+    pub fn unpack(&self) -> PackingResult<Header> {
+        Header::unpack(&self.0)
+    }
+}
+
+impl From<&Header> for Packed {
+    #[inline]
+    fn from(header: &Header) -> Self {
+        // every field is fixed-size and every `Type` is valid, so this can't fail
+        Self(header.pack().expect("packing lifecycle header"))
+    }
+}
+
+impl Display for Packed {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.unpack() {
+            Ok(header) => write!(f, "{header}"),
+            Err(_) => write!(f, "invalid lifecycle event {}", hex::encode(self.0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn packs_to_wire_form() {
+        let header = Header {
+            timestamp: MissionEpoch::new(0x0403_0201),
+            ty:        Type::SerialConnected,
+        };
+
+        let packed = Packed::from(&header);
+
+        assert_eq!(packed.0, [0x01, 0x02, 0x03, 0x04, Type::SerialConnected as u8]);
+        assert_eq!(packed.unpack().unwrap(), header);
+        assert_eq!(packed.to_string(), header.to_string());
+    }
+}
@@ -1,24 +1,122 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+    },
+    ops::{
+        BitOr,
+        BitOrAssign,
+    },
+};
+
 use packed_struct::prelude::*;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, PackedStruct)]
+/// Compact relay health frame.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PackedStruct, serde::Serialize, serde::Deserialize)]
 #[packed_struct(bit_numbering = "msb0", size_bytes = "3", endian = "msb")]
 pub struct RealtimeStatus {
+    /// Process memory usage, scaled over [`RealtimeStatus::MEMORY_RANGE_MB`].
     pub memory_usage: u8,
+    /// Downlink messages waiting to be sent (saturating).
     pub logs_pending: u8,
 
-    #[packed_field(size_bytes = "1", ty = "enum")]
+    #[packed_field(size_bytes = "1")]
     pub flags: Flags,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
-pub enum Flags {
-    None                = 0x0,
-    Booted              = 0x01,
-    Shutdown            = 0x02,
-    SerialError         = 0x04,
-    SocketError         = 0x08,
-    DownlinkPacketError = 0x10,
-    UplinkPacketError   = 0x20,
+/// Set of status flags, packed as a bitmask.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    PackedStruct,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+#[packed_struct(size_bytes = "1")]
+#[serde(transparent)]
+pub struct Flags {
+    bits: u8,
+}
+
+impl Flags {
+    pub const BOOTED: Flags = Flags::from_bits(0x01);
+    pub const DOWNLINK_PACKET_ERROR: Flags = Flags::from_bits(0x10);
+    const NAMES: [(Flags, &'static str); 6] = [
+        (Flags::BOOTED, "booted"),
+        (Flags::SHUTDOWN, "shutdown"),
+        (Flags::SERIAL_ERROR, "serial error"),
+        (Flags::SOCKET_ERROR, "socket error"),
+        (Flags::DOWNLINK_PACKET_ERROR, "downlink packet error"),
+        (Flags::UPLINK_PACKET_ERROR, "uplink packet error"),
+    ];
+    pub const NONE: Flags = Flags::from_bits(0x0);
+    pub const SERIAL_ERROR: Flags = Flags::from_bits(0x04);
+    pub const SHUTDOWN: Flags = Flags::from_bits(0x02);
+    pub const SOCKET_ERROR: Flags = Flags::from_bits(0x08);
+    pub const UPLINK_PACKET_ERROR: Flags = Flags::from_bits(0x20);
+
+    #[inline]
+    pub const fn from_bits(bits: u8) -> Self {
+        Self {
+            bits,
+        }
+    }
+
+    #[inline]
+    pub const fn bits(self) -> u8 {
+        self.bits
+    }
+
+    #[inline]
+    pub const fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Whether every flag in `other` is set.
+    #[inline]
+    pub const fn contains(self, other: Flags) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+impl BitOr for Flags {
+    type Output = Flags;
+
+    #[inline]
+    fn bitor(self, rhs: Flags) -> Flags {
+        Flags::from_bits(self.bits | rhs.bits)
+    }
+}
+
+impl BitOrAssign for Flags {
+    #[inline]
+    fn bitor_assign(&mut self, rhs: Flags) {
+        self.bits |= rhs.bits;
+    }
+}
+
+impl Display for Flags {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+
+        let known = Self::NAMES
+            .iter()
+            .filter(|(flag, _)| self.contains(*flag))
+            .map(|(_, name)| name.to_string());
+
+        let unknown = self.bits & !Self::NAMES.iter().fold(0, |acc, (flag, _)| acc | flag.bits);
+        let names =
+            known.chain((unknown != 0).then(|| format!("{unknown:#04x}"))).collect::<Vec<_>>();
+
+        f.write_str(&names.join(", "))
+    }
 }
 
 impl RealtimeStatus {
@@ -27,6 +125,71 @@ impl RealtimeStatus {
 
     #[inline]
     pub fn memory_mb(&self) -> f64 {
-        self.memory_usage as f64 * Self::MEMORY_RANGE_SIZE + Self::MEMORY_RANGE_MB.start
+        self.memory_usage as f64 / u8::MAX as f64 * Self::MEMORY_RANGE_SIZE
+            + Self::MEMORY_RANGE_MB.start
+    }
+
+    /// Scale `mb` onto [`RealtimeStatus::memory_usage`], clamping to the representable range.
+    #[inline]
+    pub fn scale_memory(mb: f64) -> u8 {
+        let fraction = (mb - Self::MEMORY_RANGE_MB.start) / Self::MEMORY_RANGE_SIZE;
+
+        (fraction.clamp(0., 1.) * u8::MAX as f64).round() as u8
+    }
+}
+
+impl Display for RealtimeStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "memory: {:.1}MB, downlink pending: {}, flags: {}",
+            self.memory_mb(),
+            self.logs_pending,
+            self.flags
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    #[test]
+    fn flags_pack_as_bitmask() {
+        let status = RealtimeStatus {
+            memory_usage: 0,
+            logs_pending: 3,
+            flags:        Flags::BOOTED | Flags::SERIAL_ERROR,
+        };
+
+        assert_eq!(status.pack().unwrap(), [0, 3, 0x05]);
+        assert_eq!(status.flags.to_string(), "booted, serial error");
+        assert_eq!(Flags::NONE.to_string(), "none");
+    }
+
+    proptest! {
+        #[test]
+        fn roundtrip(memory_usage: u8, logs_pending: u8, flags: u8) {
+            let status = RealtimeStatus {
+                memory_usage,
+                logs_pending,
+                flags: Flags::from_bits(flags),
+            };
+
+            assert_eq!(RealtimeStatus::unpack(&status.pack().unwrap()).unwrap(), status);
+        }
+
+        #[test]
+        fn memory_scale(mb in 0.0..64.0f64) {
+            let scaled = RealtimeStatus {
+                memory_usage: RealtimeStatus::scale_memory(mb),
+                logs_pending: 0,
+                flags:        Flags::NONE,
+            };
+
+            assert!((scaled.memory_mb() - mb).abs() <= RealtimeStatus::MEMORY_RANGE_SIZE / 255.);
+        }
     }
 }
@@ -0,0 +1,236 @@
+//! Time-tagged command payloads (`Event::FESchedule`).
+//!
+//! The ground uplinks a command for the ant or the central station together with the time it
+//! should be sent. The frontend holds it until then.
+
+use std::time::Duration;
+
+use bytes::Bytes;
+use packed_struct::{
+    prelude::*,
+    PackedStructSlice,
+};
+
+use crate::{
+    header::{
+        Destination,
+        Event,
+    },
+    MissionEpoch,
+};
+
+/// When a scheduled command should be sent.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum When {
+    At(MissionEpoch),
+    /// Relative to when the frontend receives the schedule request. Millisecond resolution.
+    After(Duration),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
+pub enum WhenKind {
+    At    = 0x00,
+    After = 0x01,
+}
+
+/// Fixed part of an [`Command::Add`]. Followed by the command payload.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PackedStruct)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "7", endian = "lsb")]
+pub struct AddHeader {
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub kind:        WhenKind,
+    /// Mission epoch (`At`) or milliseconds (`After`).
+    pub time:        u32,
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub destination: Destination,
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub event:       Event,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
+pub enum Op {
+    Add    = 0x01,
+    List   = 0x02,
+    Cancel = 0x03,
+    Clear  = 0x04,
+}
+
+/// A scheduler command from the ground. Encoded as a one-byte [`Op`] followed by the op-specific
+/// fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Command {
+    /// Send `event` to `destination` with `payload` at `when`.
+    Add {
+        when:        When,
+        destination: Destination,
+        event:       Event,
+        payload:     Bytes,
+    },
+
+    /// Report the pending entries.
+    List,
+
+    /// Drop the entry with the given id.
+    Cancel {
+        id: u32,
+    },
+
+    /// Drop all pending entries.
+    Clear,
+}
+
+impl Command {
+    pub fn pack_to_vec(&self) -> PackingResult<Vec<u8>> {
+        let mut out = vec![];
+
+        let op = match self {
+            Command::Add {
+                ..
+            } => Op::Add,
+            Command::List => Op::List,
+            Command::Cancel {
+                ..
+            } => Op::Cancel,
+            Command::Clear => Op::Clear,
+        };
+
+        out.push(op.to_primitive());
+
+        match self {
+            Command::Add {
+                when,
+                destination,
+                event,
+                payload,
+            } => {
+                let (kind, time) = match *when {
+                    When::At(at) => (WhenKind::At, at.into()),
+                    When::After(delay) => (
+                        WhenKind::After,
+                        u32::try_from(delay.as_millis()).map_err(|_| PackingError::InvalidValue)?,
+                    ),
+                };
+
+                let hdr = AddHeader {
+                    kind,
+                    time,
+                    destination: *destination,
+                    event: *event,
+                };
+
+                out.extend_from_slice(&hdr.pack()?);
+                out.extend_from_slice(payload);
+            },
+            Command::Cancel {
+                id,
+            } => out.extend_from_slice(&id.to_le_bytes()),
+            Command::List | Command::Clear => {},
+        }
+
+        Ok(out)
+    }
+
+    pub fn unpack_from_slice(src: &[u8]) -> PackingResult<Self> {
+        let (&op, rest) = src.split_first().ok_or(PackingError::BufferTooSmall)?;
+        let op = Op::from_primitive(op).ok_or(PackingError::InvalidValue)?;
+
+        let fixed = |len: usize| {
+            if rest.len() != len {
+                return Err(PackingError::BufferSizeMismatch {
+                    expected: len,
+                    actual:   rest.len(),
+                });
+            }
+
+            Ok(rest)
+        };
+
+        Ok(match op {
+            Op::Add => {
+                if rest.len() < 7 {
+                    return Err(PackingError::BufferTooSmall);
+                }
+
+                let (hdr, payload) = rest.split_at(7);
+                let hdr = AddHeader::unpack_from_slice(hdr)?;
+
+                let when = match hdr.kind {
+                    WhenKind::At => When::At(hdr.time.into()),
+                    WhenKind::After => When::After(Duration::from_millis(hdr.time as u64)),
+                };
+
+                Command::Add {
+                    when,
+                    destination: hdr.destination,
+                    event: hdr.event,
+                    payload: Bytes::copy_from_slice(payload),
+                }
+            },
+            Op::List => {
+                fixed(0)?;
+                Command::List
+            },
+            Op::Cancel => Command::Cancel {
+                id: u32::from_le_bytes(fixed(4)?.try_into().unwrap()),
+            },
+            Op::Clear => {
+                fixed(0)?;
+                Command::Clear
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn pack_unpack_equivalence(cmd in command_strategy()) {
+            let packed = cmd.pack_to_vec().unwrap();
+            let unpacked = Command::unpack_from_slice(&packed).unwrap();
+
+            assert_eq!(cmd, unpacked);
+        }
+
+        #[test]
+        fn unpack_arbitrary(data in prop::collection::vec(any::<u8>(), 0..64)) {
+            if let Ok(cmd) = Command::unpack_from_slice(&data) {
+                let packed = cmd.pack_to_vec().unwrap();
+                assert_eq!(Command::unpack_from_slice(&packed).unwrap(), cmd);
+            }
+        }
+    }
+
+    fn when_strategy() -> impl Strategy<Value = When> {
+        prop_oneof![
+            any::<u32>().prop_map(|t| When::At(t.into())),
+            any::<u32>().prop_map(|ms| When::After(Duration::from_millis(ms as u64))),
+        ]
+    }
+
+    fn command_strategy() -> impl Strategy<Value = Command> {
+        prop_oneof![
+            (
+                when_strategy(),
+                prop_oneof![Just(Destination::Ant), Just(Destination::CentralStation)],
+                prop_oneof![Just(Event::AntCalibrate), Just(Event::AntStart), Just(Event::CSPing)],
+                prop::collection::vec(any::<u8>(), 0..32),
+            )
+                .prop_map(|(when, destination, event, payload)| Command::Add {
+                    when,
+                    destination,
+                    event,
+                    payload: Bytes::from(payload),
+                }),
+            Just(Command::List),
+            any::<u32>().prop_map(|id| Command::Cancel {
+                id
+            }),
+            Just(Command::Clear),
+        ]
+    }
+}
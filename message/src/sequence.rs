@@ -0,0 +1,380 @@
+//! Stored command sequence payloads (`Event::FESequence`).
+//!
+//! The ground uplinks a named list of [`Step`]s once; the frontend stores it and runs the whole
+//! list when asked.
+
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+    },
+    time::Duration,
+};
+
+use packed_struct::{
+    prelude::*,
+    PackedStructSlice,
+};
+
+use crate::header::{
+    Destination,
+    Event,
+};
+
+/// What a step waits for before the sequence moves on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Wait {
+    /// Move on as soon as the command is sent. A step with no command and no wait pauses the
+    /// sequence for its timeout.
+    Nothing,
+
+    /// The step's command is acked.
+    Ack,
+
+    /// The given event arrives from the central station.
+    Event(Event),
+}
+
+/// A single step of a sequence.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Step {
+    /// Command to send, if any.
+    pub command: Option<(Destination, Event)>,
+    pub wait:    Wait,
+    /// How long to wait for the condition. Millisecond resolution.
+    pub timeout: Duration,
+    /// Additional attempts at an acked command.
+    pub retries: u8,
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.command, self.wait) {
+            (None, Wait::Nothing) => return write!(f, "pause {:?}", self.timeout),
+            (Some((dest, event)), _) => write!(f, "send {event:?} -> {dest:?}")?,
+            (None, _) => write!(f, "wait")?,
+        }
+
+        match self.wait {
+            Wait::Nothing => Ok(()),
+            Wait::Ack => write!(f, ", await ack ({:?}, {} retries)", self.timeout, self.retries),
+            Wait::Event(event) => write!(f, ", await {event:?} ({:?})", self.timeout),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
+pub enum WaitKind {
+    Nothing = 0x00,
+    Ack     = 0x01,
+    Event   = 0x02,
+}
+
+/// Packed size of a [`Step`].
+const STEP_BYTES: usize = 9;
+
+/// Wire form of a [`Step`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PackedStruct)]
+#[packed_struct(bit_numbering = "msb0", size_bytes = "9", endian = "lsb")]
+pub struct PackedStep {
+    /// A [`Destination`], or 0 if the step sends no command.
+    pub destination: u8,
+    pub event:       u8,
+    #[packed_field(size_bytes = "1", ty = "enum")]
+    pub wait:        WaitKind,
+    pub wait_event:  u8,
+    /// Milliseconds.
+    pub timeout:     u32,
+    pub retries:     u8,
+}
+
+impl Step {
+    pub fn to_packed(&self) -> PackingResult<PackedStep> {
+        let (destination, event) = match self.command {
+            Some((dest, event)) => (dest.to_primitive(), event.to_primitive()),
+            None => (0, 0),
+        };
+
+        let (wait, wait_event) = match self.wait {
+            Wait::Nothing => (WaitKind::Nothing, 0),
+            Wait::Ack => (WaitKind::Ack, 0),
+            Wait::Event(event) => (WaitKind::Event, event.to_primitive()),
+        };
+
+        Ok(PackedStep {
+            destination,
+            event,
+            wait,
+            wait_event,
+            timeout: u32::try_from(self.timeout.as_millis())
+                .map_err(|_| PackingError::InvalidValue)?,
+            retries: self.retries,
+        })
+    }
+
+    pub fn from_packed(packed: PackedStep) -> PackingResult<Self> {
+        let event = |e| Event::from_primitive(e).ok_or(PackingError::InvalidValue);
+
+        let command = match packed.destination {
+            0 => None,
+            dest => Some((
+                Destination::from_primitive(dest).ok_or(PackingError::InvalidValue)?,
+                event(packed.event)?,
+            )),
+        };
+
+        let wait = match packed.wait {
+            WaitKind::Nothing => Wait::Nothing,
+            WaitKind::Ack => Wait::Ack,
+            WaitKind::Event => Wait::Event(event(packed.wait_event)?),
+        };
+
+        Ok(Self {
+            command,
+            wait,
+            timeout: Duration::from_millis(packed.timeout as u64),
+            retries: packed.retries,
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PrimitiveEnum_u8)]
+pub enum Op {
+    Define = 0x01,
+    Delete = 0x02,
+    Run    = 0x03,
+    Abort  = 0x04,
+    List   = 0x05,
+}
+
+/// A sequence command from the ground. Encoded as a one-byte [`Op`] followed by the op-specific
+/// fields. Names are prefixed with their length in one byte.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Command {
+    /// Store `steps` under `name`, replacing any sequence already stored with that name.
+    Define {
+        name:  String,
+        steps: Vec<Step>,
+    },
+
+    Delete {
+        name: String,
+    },
+
+    Run {
+        name: String,
+    },
+
+    /// Stop the running sequence.
+    Abort,
+
+    /// Report the stored sequences.
+    List,
+}
+
+fn pack_name(out: &mut Vec<u8>, name: &str) -> PackingResult<()> {
+    let len = u8::try_from(name.len()).map_err(|_| PackingError::InvalidValue)?;
+
+    out.push(len);
+    out.extend_from_slice(name.as_bytes());
+
+    Ok(())
+}
+
+fn unpack_name(src: &[u8]) -> PackingResult<(String, &[u8])> {
+    let (&len, rest) = src.split_first().ok_or(PackingError::BufferTooSmall)?;
+
+    if rest.len() < len as usize {
+        return Err(PackingError::BufferTooSmall);
+    }
+
+    let (name, rest) = rest.split_at(len as usize);
+    let name = String::from_utf8(name.to_vec()).map_err(|_| PackingError::InvalidValue)?;
+
+    Ok((name, rest))
+}
+
+impl Command {
+    pub fn pack_to_vec(&self) -> PackingResult<Vec<u8>> {
+        let mut out = vec![];
+
+        let op = match self {
+            Command::Define {
+                ..
+            } => Op::Define,
+            Command::Delete {
+                ..
+            } => Op::Delete,
+            Command::Run {
+                ..
+            } => Op::Run,
+            Command::Abort => Op::Abort,
+            Command::List => Op::List,
+        };
+
+        out.push(op.to_primitive());
+
+        match self {
+            Command::Define {
+                name,
+                steps,
+            } => {
+                pack_name(&mut out, name)?;
+
+                for step in steps {
+                    out.extend_from_slice(&step.to_packed()?.pack()?);
+                }
+            },
+            Command::Delete {
+                name,
+            }
+            | Command::Run {
+                name,
+            } => pack_name(&mut out, name)?,
+            Command::Abort | Command::List => {},
+        }
+
+        Ok(out)
+    }
+
+    pub fn unpack_from_slice(src: &[u8]) -> PackingResult<Self> {
+        let (&op, rest) = src.split_first().ok_or(PackingError::BufferTooSmall)?;
+        let op = Op::from_primitive(op).ok_or(PackingError::InvalidValue)?;
+
+        let name_only = || {
+            let (name, rest) = unpack_name(rest)?;
+
+            if !rest.is_empty() {
+                return Err(PackingError::BufferSizeMismatch {
+                    expected: 0,
+                    actual:   rest.len(),
+                });
+            }
+
+            Ok(name)
+        };
+
+        let empty = || {
+            if !rest.is_empty() {
+                return Err(PackingError::BufferSizeMismatch {
+                    expected: 0,
+                    actual:   rest.len(),
+                });
+            }
+
+            Ok(())
+        };
+
+        Ok(match op {
+            Op::Define => {
+                let (name, steps) = unpack_name(rest)?;
+
+                if steps.len() % STEP_BYTES != 0 {
+                    return Err(PackingError::BufferSizeMismatch {
+                        expected: steps.len() - steps.len() % STEP_BYTES,
+                        actual:   steps.len(),
+                    });
+                }
+
+                let steps = steps
+                    .chunks(STEP_BYTES)
+                    .map(|step| Step::from_packed(PackedStep::unpack_from_slice(step)?))
+                    .collect::<PackingResult<Vec<_>>>()?;
+
+                Command::Define {
+                    name,
+                    steps,
+                }
+            },
+            Op::Delete => Command::Delete {
+                name: name_only()?,
+            },
+            Op::Run => Command::Run {
+                name: name_only()?,
+            },
+            Op::Abort => {
+                empty()?;
+                Command::Abort
+            },
+            Op::List => {
+                empty()?;
+                Command::List
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn pack_unpack_equivalence(cmd in command_strategy()) {
+            let packed = cmd.pack_to_vec().unwrap();
+            let unpacked = Command::unpack_from_slice(&packed).unwrap();
+
+            assert_eq!(cmd, unpacked);
+        }
+
+        #[test]
+        fn unpack_arbitrary(data in prop::collection::vec(any::<u8>(), 0..64)) {
+            if let Ok(cmd) = Command::unpack_from_slice(&data) {
+                let packed = cmd.pack_to_vec().unwrap();
+                assert_eq!(Command::unpack_from_slice(&packed).unwrap(), cmd);
+            }
+        }
+    }
+
+    fn event_strategy() -> impl Strategy<Value = Event> {
+        prop_oneof![
+            Just(Event::CSPing),
+            Just(Event::CSGarageOpen),
+            Just(Event::CSBLEConnect),
+            Just(Event::AntPing),
+        ]
+    }
+
+    fn step_strategy() -> impl Strategy<Value = Step> {
+        let command = prop::option::of((
+            prop_oneof![Just(Destination::Ant), Just(Destination::CentralStation)],
+            event_strategy(),
+        ));
+
+        let wait = prop_oneof![
+            Just(Wait::Nothing),
+            Just(Wait::Ack),
+            event_strategy().prop_map(Wait::Event),
+        ];
+
+        (command, wait, any::<u32>(), any::<u8>()).prop_map(|(command, wait, timeout, retries)| {
+            Step {
+                command,
+                wait,
+                timeout: Duration::from_millis(timeout as u64),
+                retries,
+            }
+        })
+    }
+
+    fn command_strategy() -> impl Strategy<Value = Command> {
+        prop_oneof![
+            ("[a-z-]{0,16}", prop::collection::vec(step_strategy(), 0..8)).prop_map(
+                |(name, steps)| Command::Define {
+                    name,
+                    steps,
+                }
+            ),
+            "[a-z-]{0,16}".prop_map(|name| Command::Delete {
+                name
+            }),
+            "[a-z-]{0,16}".prop_map(|name| Command::Run {
+                name
+            }),
+            Just(Command::Abort),
+            Just(Command::List),
+        ]
+    }
+}
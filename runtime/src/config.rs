@@ -0,0 +1,106 @@
+use std::{
+    fs,
+    io,
+    path::Path,
+};
+
+use crate::{
+    dfu,
+    dump,
+    ground::uplink,
+    housekeeping,
+    limits,
+    scheduler,
+    sequencer,
+    serial::{
+        queue,
+        raw,
+        watchdog,
+    },
+    thermostat,
+    timing::Timing,
+};
+
+/// Runtime configuration, loaded from a JSON file. Every section is optional and falls back to its
+/// defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub timing:       Timing,
+    pub dfu:          dfu::Config,
+    pub watchdog:     watchdog::Config,
+    pub uplink:       uplink::Config,
+    pub scheduler:    scheduler::Config,
+    pub sequencer:    sequencer::Config,
+    pub limits:       limits::Config,
+    pub thermostat:   thermostat::Config,
+    pub dump:         dump::Config,
+    pub housekeeping: housekeeping::Config,
+    pub serial_queue: queue::Config,
+    pub serial:       raw::Config,
+}
+
+/// A configuration parameter that's out of range, reported by each section's `validate`.
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("invalid parameter {name}: {reason}")]
+pub struct Error {
+    pub name:   &'static str,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Format(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Param(#[from] Error),
+}
+
+impl Config {
+    #[tracing::instrument(skip(path), fields(path = %path.as_ref().display()), err(Display))]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LoadError> {
+        let data = fs::read(path.as_ref())?;
+        let config: Config = serde_json::from_slice(&data)?;
+
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        self.timing.validate()?;
+        self.dfu.validate()?;
+        self.watchdog.validate()?;
+        self.uplink.validate()?;
+        self.scheduler.validate()?;
+        self.sequencer.validate()?;
+        self.limits.validate()?;
+        self.thermostat.validate()?;
+        self.dump.validate()?;
+        self.housekeeping.validate()?;
+        self.serial_queue.validate()?;
+        self.serial.validate()?;
+
+        Ok(())
+    }
+
+    /// Report the configuration in effect.
+    pub fn log(&self) {
+        self.timing.log();
+        self.dfu.log();
+        self.watchdog.log();
+        self.uplink.log();
+        self.scheduler.log();
+        self.sequencer.log();
+        self.limits.log();
+        self.thermostat.log();
+        self.dump.log();
+        self.housekeeping.log();
+        self.serial_queue.log();
+        self.serial.log();
+    }
+}
@@ -0,0 +1,346 @@
+use std::sync::Once;
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+use bytes::Bytes;
+use packed_struct::{
+    PackedStruct,
+    PackingResult,
+};
+
+use message::{
+    dfu::{
+        ChunkHeader,
+        Command,
+        ImageInfo,
+    },
+    downlink::dfu::Report,
+    header::{
+        Destination,
+        Event,
+    },
+};
+
+use crate::{
+    dfu::{
+        Config,
+        Image,
+        Progress,
+        Staging,
+    },
+    ground,
+    params,
+    serial,
+    table::Retry,
+};
+
+/// Have the ant enter OTA mode, then tell the central station to install the image it was streamed
+/// on the ant over BLE.
+async fn apply(retry: Retry) -> Result<(), serial::Error> {
+    let steps =
+        [(Destination::Ant, Event::AntOTA), (Destination::CentralStation, Event::CSDFUViaBLE)];
+
+    for (destination, event) in steps {
+        serial::send_retry(
+            move || Box::pin(async move { message::command(&params().await, destination, event) }),
+            retry.timeout,
+            retry.strategy(),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Receives ant firmware images from the ground (`FEAntDFU`) and streams them to the central
+/// station as acked `CSAntDFUInit` / `CSAntDFUPacket` commands. Once the whole image is acked, the
+/// central station is told to install it on the ant (`AntOTA`, `CSDFUViaBLE`).
+pub struct AntDfu {
+    config:         Config,
+    subscribe_once: Once,
+    staging:        Staging,
+    /// Whether the central station has acked `CSAntDFUInit` for the current image.
+    initialized:    bool,
+    /// Image bytes acked by the central station.
+    sent:           u32,
+    /// Whether the streamed image has been installed on the ant.
+    applied:        bool,
+    streaming:      Option<SpawnHandle>,
+    progress:       Progress,
+}
+
+impl Default for AntDfu {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl AntDfu {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            staging: Default::default(),
+            initialized: false,
+            sent: 0,
+            applied: false,
+            streaming: None,
+            progress: Default::default(),
+        }
+    }
+
+    #[inline]
+    fn report(&self, report: Report) {
+        self.issue_system_async(ground::AntDfuReport(report));
+    }
+
+    fn reject(&self, reason: impl ToString) {
+        let reason = reason.to_string();
+
+        tracing::warn!(%reason, "rejecting ant dfu command");
+        self.report(Report::Rejected(reason));
+    }
+
+    fn stop_streaming(&mut self, ctx: &mut Context<Self>) {
+        if let Some(handle) = self.streaming.take() {
+            ctx.cancel_future(handle);
+        }
+    }
+
+    #[tracing::instrument(skip(self, ctx))]
+    fn begin(&mut self, info: ImageInfo, ctx: &mut Context<Self>) {
+        self.stop_streaming(ctx);
+
+        self.initialized = false;
+        self.sent = 0;
+        self.applied = false;
+
+        let report = self.staging.begin(info, self.config.max_image_size);
+        self.report(report);
+    }
+
+    #[tracing::instrument(skip(self, data), fields(len = data.len()))]
+    fn chunk(&mut self, offset: u32, data: Bytes) {
+        if let Some(report) = self.staging.chunk(offset, &data) {
+            self.report(report);
+        }
+    }
+
+    #[tracing::instrument(skip(self, ctx))]
+    fn flash(&mut self, from_start: bool, ctx: &mut Context<Self>) {
+        let size = match self.staging.verified() {
+            Ok(image) => image.info().size,
+            Err(reason) => return self.reject(reason),
+        };
+
+        if self.streaming.is_some() {
+            tracing::info!("already streaming image");
+            return;
+        }
+
+        // an image that was streamed but never applied is applied without streaming it again
+        if from_start || self.applied {
+            self.initialized = false;
+            self.sent = 0;
+            self.applied = false;
+        }
+
+        tracing::info!(sent = self.sent, "streaming ant firmware image to central station");
+
+        self.progress.reset(self.sent);
+        self.stream_next(ctx);
+    }
+
+    #[tracing::instrument(skip(self, ctx))]
+    fn abort(&mut self, ctx: &mut Context<Self>) {
+        self.stop_streaming(ctx);
+
+        self.staging.clear();
+        self.initialized = false;
+        self.sent = 0;
+        self.applied = false;
+
+        tracing::info!("ant dfu aborted");
+        self.report(Report::Aborted);
+    }
+
+    /// The next command to send to the central station and the progress it represents once acked.
+    fn next_packet(&self, image: &Image) -> PackingResult<(Event, Vec<u8>, u32)> {
+        let info = image.info();
+
+        if !self.initialized {
+            return Ok((Event::CSAntDFUInit, info.pack()?.to_vec(), 0));
+        }
+
+        let end = self.sent.saturating_add(self.config.packet_size).min(info.size);
+
+        let mut payload = ChunkHeader {
+            offset: self.sent,
+        }
+        .pack()?
+        .to_vec();
+
+        payload.extend_from_slice(&image.data()[self.sent as usize..end as usize]);
+
+        Ok((Event::CSAntDFUPacket, payload, end))
+    }
+
+    fn stream_next(&mut self, ctx: &mut Context<Self>) {
+        let Ok(info) = self.staging.verified().map(Image::info) else {
+            return;
+        };
+
+        if self.initialized && self.sent >= info.size {
+            tracing::info!(size = info.size, "ant firmware image streamed to central station");
+
+            self.apply(info, ctx);
+            return;
+        }
+
+        let Ok(image) = self.staging.verified() else {
+            return;
+        };
+
+        let (event, payload, end) = match self.next_packet(image) {
+            Ok(x) => x,
+            Err(e) => {
+                tracing::error!(error = %e, "packing ant dfu packet");
+                return;
+            },
+        };
+
+        let payload = Bytes::from(payload);
+        let retry = self.config.retry;
+
+        let fut = fut::wrap_future(serial::send_retry(
+            move || {
+                let payload = payload.clone();
+
+                Box::pin(async move {
+                    message::command_with_payload(
+                        &params().await,
+                        Destination::CentralStation,
+                        event,
+                        payload,
+                    )
+                })
+            },
+            retry.timeout,
+            retry.strategy(),
+        ))
+        .map(move |result, act: &mut Self, ctx| {
+            act.streaming = None;
+
+            if let Err(e) = result {
+                tracing::error!(error = %e, ?event, sent = act.sent, "ant dfu stalled");
+
+                act.report(Report::Stalled {
+                    sent:  act.sent,
+                    size:  info.size,
+                    error: e.to_string(),
+                });
+
+                return;
+            }
+
+            if event == Event::CSAntDFUInit {
+                act.initialized = true;
+            } else {
+                act.sent = end;
+
+                if act.progress.should_report(end, info.size) {
+                    act.report(Report::Streaming {
+                        sent: end,
+                        size: info.size,
+                    });
+                }
+            }
+
+            act.stream_next(ctx);
+        });
+
+        self.streaming = Some(ctx.spawn(fut));
+    }
+
+    fn apply(&mut self, info: ImageInfo, ctx: &mut Context<Self>) {
+        self.report(Report::Applying);
+
+        let fut = fut::wrap_future(apply(self.config.apply_retry)).map(
+            move |result, act: &mut Self, _ctx| {
+                act.streaming = None;
+
+                if let Err(e) = result {
+                    tracing::error!(error = %e, "applying ant firmware image");
+                    act.report(Report::ApplyFailed(e.to_string()));
+
+                    return;
+                }
+
+                tracing::info!(size = info.size, "ant firmware image applied");
+                act.applied = true;
+
+                act.report(Report::Complete {
+                    size:     info.size,
+                    checksum: info.checksum,
+                });
+            },
+        );
+
+        self.streaming = Some(ctx.spawn(fut));
+    }
+}
+
+impl Actor for AntDfu {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, ground::UpCommand>(ctx);
+        });
+
+        if self.streaming.take().is_some() {
+            tracing::warn!(sent = self.sent, "ant dfu interrupted by restart, flash to resume");
+        }
+
+        tracing::info!("ant dfu started");
+    }
+}
+
+impl Supervised for AntDfu {
+    fn restarting(&mut self, _ctx: &mut <Self as Actor>::Context) {
+        tracing::warn!("ant dfu restarting");
+    }
+}
+
+impl Handler<ground::UpCommand> for AntDfu {
+    type Result = ();
+
+    #[tracing::instrument(skip_all)]
+    fn handle(&mut self, ground::UpCommand(msg): ground::UpCommand, ctx: &mut Self::Context) {
+        if msg.header.header.ty.event != Event::FEAntDFU {
+            return;
+        }
+
+        let cmd = match Command::unpack_from_slice(msg.payload.as_ref()) {
+            Ok(cmd) => cmd,
+            Err(e) => return self.reject(format!("bad command payload: {e}")),
+        };
+
+        match cmd {
+            Command::Begin(info) => self.begin(info, ctx),
+            Command::Chunk {
+                offset,
+                data,
+            } => self.chunk(offset, data),
+            Command::Flash {
+                from_start,
+            } => self.flash(from_start, ctx),
+            Command::Abort => self.abort(ctx),
+        }
+    }
+}
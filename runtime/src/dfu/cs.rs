@@ -0,0 +1,382 @@
+use std::{
+    sync::Once,
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    Broker,
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+use bytes::Bytes;
+use packed_struct::{
+    PackedStruct,
+    PackedStructSlice,
+    PackingError,
+};
+use tokio::sync::mpsc;
+
+use message::{
+    dfu::{
+        BootHeader,
+        BootOp,
+        BootResponse,
+        BootStatus,
+        ChunkHeader,
+        Command,
+        ImageInfo,
+    },
+    downlink::dfu::Report,
+    header::{
+        Destination,
+        Event,
+    },
+};
+
+use crate::{
+    dfu::{
+        Config,
+        Progress,
+        Staging,
+    },
+    ground,
+    params,
+    serial,
+    serial::raw,
+    table::Retry,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("requesting bootloader: {0}")]
+    Enter(#[from] serial::Error),
+
+    #[error("serial link lost")]
+    LinkLost,
+
+    #[error("no ack from bootloader for {op:?} frame {seq}")]
+    NoAck {
+        op:  BootOp,
+        seq: u16,
+    },
+
+    #[error("packing bootloader frame: {0}")]
+    Packing(#[from] PackingError),
+}
+
+#[inline]
+fn report(report: Report) {
+    Broker::<SystemBroker>::issue_async(ground::CsDfuReport(report));
+}
+
+/// Frame exchange with the central station bootloader over a serial port in maintenance mode.
+struct Bootloader {
+    rx:    mpsc::UnboundedReceiver<Bytes>,
+    retry: Retry,
+    seq:   u16,
+}
+
+impl Bootloader {
+    /// Send a frame, retrying until the bootloader acks it.
+    #[tracing::instrument(skip(self, payload), fields(len = payload.len()), err(Display))]
+    async fn request(&mut self, op: BootOp, payload: &[u8]) -> Result<(), Error> {
+        self.seq = self.seq.wrapping_add(1);
+        let seq = self.seq;
+
+        let mut frame = BootHeader {
+            op,
+            seq,
+        }
+        .pack()?
+        .to_vec();
+
+        frame.extend_from_slice(payload);
+        let frame = Bytes::from(frame);
+
+        for delay in std::iter::once(Duration::ZERO).chain(self.retry.strategy()) {
+            tokio::time::sleep(delay).await;
+            Broker::<SystemBroker>::issue_async(raw::MaintenancePacket(frame.clone()));
+
+            match self.response(seq).await? {
+                Some(BootStatus::Ack) => return Ok(()),
+                Some(BootStatus::Nak) => tracing::warn!(seq, "bootloader nak, retrying"),
+                None => tracing::warn!(seq, "bootloader response timed out, retrying"),
+            }
+        }
+
+        Err(Error::NoAck {
+            op,
+            seq,
+        })
+    }
+
+    /// Wait for the response to frame `seq`. `None` if it doesn't arrive in time.
+    async fn response(&mut self, seq: u16) -> Result<Option<BootStatus>, Error> {
+        let deadline = tokio::time::Instant::now() + self.retry.timeout;
+
+        loop {
+            let frame = match tokio::time::timeout_at(deadline, self.rx.recv()).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return Err(Error::LinkLost),
+                Err(_elapsed) => return Ok(None),
+            };
+
+            match BootResponse::unpack_from_slice(&frame) {
+                Ok(resp) if resp.seq == seq => return Ok(Some(resp.status)),
+                Ok(resp) => tracing::debug!(seq = resp.seq, "ignoring stale bootloader response"),
+                Err(e) => {
+                    let frame = hex::encode(&frame);
+                    tracing::warn!(error = %e, %frame, "bad bootloader response");
+                },
+            }
+        }
+    }
+
+    /// Tell the bootloader to give up and boot the existing firmware. Best-effort: not acked.
+    fn abort() {
+        let frame = BootHeader {
+            op:  BootOp::Abort,
+            seq: 0,
+        }
+        .pack();
+
+        match frame {
+            Ok(frame) => Broker::<SystemBroker>::issue_async(raw::MaintenancePacket(
+                Bytes::copy_from_slice(&frame),
+            )),
+            Err(e) => tracing::error!(error = %e, "packing bootloader abort"),
+        }
+    }
+}
+
+/// Send `image` to the bootloader block by block, updating `sent` as blocks are acked.
+async fn transfer(
+    bootloader: &mut Bootloader,
+    image: &[u8],
+    info: ImageInfo,
+    block_size: u32,
+    sent: &mut u32,
+) -> Result<(), Error> {
+    let mut progress = Progress::default();
+
+    bootloader.request(BootOp::Start, &info.pack()?).await?;
+
+    for block in image.chunks(block_size as usize) {
+        let mut payload = ChunkHeader {
+            offset: *sent,
+        }
+        .pack()?
+        .to_vec();
+
+        payload.extend_from_slice(block);
+        bootloader.request(BootOp::Block, &payload).await?;
+
+        *sent += block.len() as u32;
+
+        if progress.should_report(*sent, info.size) {
+            report(Report::Streaming {
+                sent: *sent,
+                size: info.size,
+            });
+        }
+    }
+
+    // the bootloader checks the whole image against `info` before acking
+    bootloader.request(BootOp::Finish, &[]).await
+}
+
+/// Reboot the central station into its bootloader, put the serial port into maintenance mode, and
+/// transfer `image`. Normal relaying is restored whether or not the transfer succeeds.
+#[tracing::instrument(skip_all, fields(size = info.size))]
+async fn flash(image: Bytes, info: ImageInfo, config: Config) -> Result<(), (u32, Error)> {
+    let retry = config.retry;
+
+    serial::send_retry(
+        || {
+            Box::pin(async {
+                message::command(&params().await, Destination::CentralStation, Event::CSDFUSerial)
+            })
+        },
+        retry.timeout,
+        retry.strategy(),
+    )
+    .await
+    .map_err(|e| (0, e.into()))?;
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    Broker::<SystemBroker>::issue_async(raw::Maintenance(Some(tx)));
+    report(Report::Bootloader);
+
+    tokio::time::sleep(config.cs_boot_delay).await;
+
+    let mut bootloader = Bootloader {
+        rx,
+        retry,
+        seq: 0,
+    };
+
+    let mut sent = 0;
+    let result = transfer(&mut bootloader, &image, info, config.cs_block_size, &mut sent).await;
+
+    if result.is_err() {
+        Bootloader::abort();
+    }
+
+    Broker::<SystemBroker>::issue_async(raw::Maintenance(None));
+
+    result.map_err(|e| (sent, e))
+}
+
+/// Receives central station firmware images from the ground (`FECSDFU`) and writes them to the
+/// central station bootloader over the serial port, which is taken out of normal relaying for the
+/// duration.
+pub struct CsDfu {
+    config:         Config,
+    subscribe_once: Once,
+    staging:        Staging,
+    flashing:       Option<SpawnHandle>,
+}
+
+impl Default for CsDfu {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl CsDfu {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            staging: Default::default(),
+            flashing: None,
+        }
+    }
+
+    fn reject(&self, reason: impl ToString) {
+        let reason = reason.to_string();
+
+        tracing::warn!(%reason, "rejecting cs dfu command");
+        report(Report::Rejected(reason));
+    }
+
+    #[tracing::instrument(skip(self, ctx))]
+    fn flash(&mut self, ctx: &mut Context<Self>) {
+        if self.flashing.is_some() {
+            tracing::info!("already flashing central station");
+            return;
+        }
+
+        let (image, info) = match self.staging.verified() {
+            Ok(image) => (Bytes::copy_from_slice(image.data()), image.info()),
+            Err(reason) => return self.reject(reason),
+        };
+
+        tracing::info!("flashing central station firmware");
+
+        let fut = fut::wrap_future(flash(image, info, self.config)).map(
+            move |result, act: &mut Self, _ctx| {
+                act.flashing = None;
+
+                match result {
+                    Ok(()) => {
+                        tracing::info!("central station firmware flashed");
+
+                        report(Report::Complete {
+                            size:     info.size,
+                            checksum: info.checksum,
+                        });
+                    },
+                    Err((sent, e)) => report(Report::Failed {
+                        sent,
+                        size: info.size,
+                        error: e.to_string(),
+                    }),
+                }
+            },
+        );
+
+        self.flashing = Some(ctx.spawn(fut));
+    }
+
+    #[tracing::instrument(skip(self, ctx))]
+    fn abort(&mut self, ctx: &mut Context<Self>) {
+        if let Some(handle) = self.flashing.take() {
+            ctx.cancel_future(handle);
+
+            Bootloader::abort();
+            self.issue_system_async(raw::Maintenance(None));
+        }
+
+        self.staging.clear();
+
+        tracing::info!("cs dfu aborted");
+        report(Report::Aborted);
+    }
+}
+
+impl Actor for CsDfu {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, ground::UpCommand>(ctx);
+        });
+
+        if self.flashing.take().is_some() {
+            tracing::warn!("cs dfu interrupted by restart, restoring serial relay");
+
+            Bootloader::abort();
+            self.issue_system_async(raw::Maintenance(None));
+        }
+
+        tracing::info!("cs dfu started");
+    }
+}
+
+impl Supervised for CsDfu {
+    fn restarting(&mut self, _ctx: &mut <Self as Actor>::Context) {
+        tracing::warn!("cs dfu restarting");
+    }
+}
+
+impl Handler<ground::UpCommand> for CsDfu {
+    type Result = ();
+
+    #[tracing::instrument(skip_all)]
+    fn handle(&mut self, ground::UpCommand(msg): ground::UpCommand, ctx: &mut Self::Context) {
+        if msg.header.header.ty.event != Event::FECSDFU {
+            return;
+        }
+
+        let cmd = match Command::unpack_from_slice(msg.payload.as_ref()) {
+            Ok(cmd) => cmd,
+            Err(e) => return self.reject(format!("bad command payload: {e}")),
+        };
+
+        match cmd {
+            Command::Begin(_)
+            | Command::Chunk {
+                ..
+            } if self.flashing.is_some() => self.reject("central station flash in progress"),
+            Command::Begin(info) => {
+                report(self.staging.begin(info, self.config.max_image_size));
+            },
+            Command::Chunk {
+                offset,
+                data,
+            } => {
+                if let Some(r) = self.staging.chunk(offset, &data) {
+                    report(r);
+                }
+            },
+            Command::Flash {
+                ..
+            } => self.flash(ctx),
+            Command::Abort => self.abort(ctx),
+        }
+    }
+}
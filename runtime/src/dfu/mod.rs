@@ -0,0 +1,365 @@
+use std::{
+    collections::BTreeMap,
+    time::Duration,
+};
+
+use message::{
+    dfu::{
+        self,
+        ImageInfo,
+    },
+    downlink::dfu::Report,
+};
+
+use crate::{
+    config,
+    table::Retry,
+};
+
+pub mod ant;
+pub mod cs;
+
+/// Progress is reported at most this many times over the course of a transfer.
+const PROGRESS_STEPS: u32 = 20;
+
+/// Firmware update parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Largest image we'll accept from the ground.
+    pub max_image_size: u32,
+
+    /// Image bytes carried by each packet streamed to the target.
+    pub packet_size: u32,
+
+    /// Retry parameters for each packet streamed to the target.
+    pub retry: Retry,
+
+    /// Retry parameters for the commands that apply a streamed ant image (`AntOTA`, then
+    /// `CSDFUViaBLE`), which take longer to be acked than a packet.
+    pub apply_retry: Retry,
+
+    /// Image bytes carried by each block sent to the central station bootloader.
+    pub cs_block_size: u32,
+
+    /// Time for the central station to reboot into its bootloader.
+    #[serde(with = "crate::serde_millis")]
+    pub cs_boot_delay: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_image_size: 1 << 20,
+            packet_size:    128,
+            retry:          Retry {
+                timeout:   Duration::from_secs(2),
+                base:      Duration::from_millis(500),
+                max_delay: Duration::from_secs(5),
+                count:     5,
+            },
+            apply_retry:    Retry {
+                timeout:   Duration::from_secs(30),
+                base:      Duration::from_secs(2),
+                max_delay: Duration::from_secs(10),
+                count:     3,
+            },
+            cs_block_size:  256,
+            cs_boot_delay:  Duration::from_secs(2),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if self.max_image_size == 0 {
+            return Err(config::Error {
+                name:   "dfu.max_image_size",
+                reason: "must be nonzero",
+            });
+        }
+
+        if self.packet_size == 0 {
+            return Err(config::Error {
+                name:   "dfu.packet_size",
+                reason: "must be nonzero",
+            });
+        }
+
+        if self.cs_block_size == 0 {
+            return Err(config::Error {
+                name:   "dfu.cs_block_size",
+                reason: "must be nonzero",
+            });
+        }
+
+        self.retry.validate("dfu.retry")?;
+        self.apply_retry.validate("dfu.apply_retry")
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            max_image_size = self.max_image_size,
+            packet_size = self.packet_size,
+            retry = ?self.retry,
+            apply_retry = ?self.apply_retry,
+            cs_block_size = self.cs_block_size,
+            cs_boot_delay = ?self.cs_boot_delay,
+            "firmware update config"
+        );
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("empty image")]
+    Empty,
+
+    #[error("{size}B image exceeds the {max}B limit")]
+    TooLarge {
+        size: u32,
+        max:  u32,
+    },
+
+    #[error("{len}B chunk at offset {offset} extends past the end of the {size}B image")]
+    OutOfBounds {
+        offset: u32,
+        len:    usize,
+        size:   u32,
+    },
+
+    #[error("image incomplete: received {received}/{size}B")]
+    Incomplete {
+        received: u32,
+        size:     u32,
+    },
+
+    #[error("checksum mismatch: expected {expected:#010x}, computed {actual:#010x}")]
+    ChecksumMismatch {
+        expected: u32,
+        actual:   u32,
+    },
+}
+
+/// A firmware image being reassembled from chunks, which may arrive out of order or more than
+/// once.
+#[derive(Debug, Clone)]
+pub struct Image {
+    info:   ImageInfo,
+    data:   Vec<u8>,
+    /// Received chunks: start offset -> end offset.
+    chunks: BTreeMap<u32, u32>,
+}
+
+impl Image {
+    pub fn new(info: ImageInfo, max_size: u32) -> Result<Self, Error> {
+        if info.size == 0 {
+            return Err(Error::Empty);
+        }
+
+        if info.size > max_size {
+            return Err(Error::TooLarge {
+                size: info.size,
+                max:  max_size,
+            });
+        }
+
+        Ok(Self {
+            info,
+            data: vec![0; info.size as usize],
+            chunks: BTreeMap::new(),
+        })
+    }
+
+    #[inline]
+    pub fn info(&self) -> ImageInfo {
+        self.info
+    }
+
+    #[inline]
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn insert(&mut self, offset: u32, chunk: &[u8]) -> Result<(), Error> {
+        let out_of_bounds = || Error::OutOfBounds {
+            offset,
+            len: chunk.len(),
+            size: self.info.size,
+        };
+
+        let end = u32::try_from(chunk.len())
+            .ok()
+            .and_then(|len| offset.checked_add(len))
+            .filter(|&end| end <= self.info.size)
+            .ok_or_else(out_of_bounds)?;
+
+        self.data[offset as usize..end as usize].copy_from_slice(chunk);
+
+        let stop = self.chunks.entry(offset).or_insert(end);
+        *stop = (*stop).max(end);
+
+        Ok(())
+    }
+
+    /// Number of distinct image bytes received so far.
+    pub fn received(&self) -> u32 {
+        let mut covered = 0;
+        let mut reached = 0;
+
+        for (&start, &stop) in &self.chunks {
+            let start = start.max(reached);
+
+            if stop > start {
+                covered += stop - start;
+                reached = stop;
+            }
+        }
+
+        covered
+    }
+
+    #[inline]
+    pub fn is_complete(&self) -> bool {
+        self.received() == self.info.size
+    }
+
+    pub fn verify(&self) -> Result<(), Error> {
+        let received = self.received();
+
+        if received != self.info.size {
+            return Err(Error::Incomplete {
+                received,
+                size: self.info.size,
+            });
+        }
+
+        let actual = dfu::checksum(&self.data);
+
+        if actual != self.info.checksum {
+            return Err(Error::ChecksumMismatch {
+                expected: self.info.checksum,
+                actual,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// Rate limiter for progress reports.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Progress {
+    reported: u32,
+}
+
+impl Progress {
+    #[inline]
+    pub fn reset(&mut self, done: u32) {
+        self.reported = done;
+    }
+
+    /// Whether `done` bytes out of `size` is far enough along since the last report to be worth
+    /// reporting.
+    pub fn should_report(&mut self, done: u32, size: u32) -> bool {
+        if done != size && done.saturating_sub(self.reported) < (size / PROGRESS_STEPS).max(1) {
+            return false;
+        }
+
+        self.reported = done;
+        true
+    }
+}
+
+/// An image being uplinked from the ground.
+#[derive(Debug, Default)]
+pub struct Staging {
+    image:    Option<Image>,
+    verified: bool,
+    progress: Progress,
+}
+
+impl Staging {
+    /// Discard any previous image and start receiving a new one.
+    pub fn begin(&mut self, info: ImageInfo, max_size: u32) -> Report {
+        self.verified = false;
+        self.progress.reset(0);
+
+        match Image::new(info, max_size) {
+            Ok(image) => {
+                tracing::info!(size = info.size, "receiving firmware image");
+                self.image = Some(image);
+
+                Report::Receiving {
+                    received: 0,
+                    size:     info.size,
+                }
+            },
+            Err(e) => {
+                self.image = None;
+                Report::Rejected(e.to_string())
+            },
+        }
+    }
+
+    /// Add a chunk to the image, returning a report if there's news worth downlinking.
+    pub fn chunk(&mut self, offset: u32, data: &[u8]) -> Option<Report> {
+        let Some(ref mut image) = self.image else {
+            return Some(Report::Rejected("chunk received with no image in progress".to_owned()));
+        };
+
+        if self.verified {
+            tracing::debug!("ignoring chunk for already-verified image");
+            return None;
+        }
+
+        if let Err(e) = image.insert(offset, data) {
+            return Some(Report::Rejected(e.to_string()));
+        }
+
+        let info = image.info();
+        let received = image.received();
+
+        if !image.is_complete() {
+            return self.progress.should_report(received, info.size).then_some(Report::Receiving {
+                received,
+                size: info.size,
+            });
+        }
+
+        match image.verify() {
+            Ok(()) => {
+                tracing::info!(size = info.size, "firmware image verified");
+                self.verified = true;
+
+                Some(Report::Verified {
+                    size:     info.size,
+                    checksum: info.checksum,
+                })
+            },
+            Err(e) => {
+                tracing::error!(error = %e, "firmware image failed verification");
+                self.image = None;
+
+                Some(Report::Rejected(e.to_string()))
+            },
+        }
+    }
+
+    /// The image, once it's been completely received and verified.
+    #[inline]
+    pub fn verified(&self) -> Result<&Image, &'static str> {
+        match self.image {
+            Some(ref image) if self.verified => Ok(image),
+            Some(_) => Err("image has not been completely received"),
+            None => Err("no image to flash"),
+        }
+    }
+
+    #[inline]
+    pub fn clear(&mut self) {
+        self.image = None;
+        self.verified = false;
+    }
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::BTreeSet,
+    sync::Once,
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+
+use message::{
+    downlink::dump::{
+        Report,
+        Stats,
+    },
+    header::Event,
+    MissionEpoch,
+    SourceInfo,
+    UniqueId,
+};
+
+use crate::{
+    config,
+    ground,
+    serial,
+};
+
+/// A dump has finished. Raised as `FEDumpComplete` at the state machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Message)]
+#[rtype(result = "()")]
+pub struct DumpComplete(pub Stats);
+
+/// Dump tracking parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// A dump is considered complete once no packets have arrived for it for this long.
+    #[serde(with = "crate::serde_millis")]
+    pub quiet: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            quiet: Duration::from_secs(5),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if self.quiet.is_zero() {
+            return Err(config::Error {
+                name:   "dump.quiet",
+                reason: "must be nonzero",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(quiet = ?self.quiet, "dump tracking config");
+    }
+}
+
+/// Signed distance from `from` to `to`, allowing for wraparound.
+#[inline]
+fn ms_between(from: MissionEpoch, to: MissionEpoch) -> i32 {
+    u32::from(to).wrapping_sub(u32::from(from)) as i32
+}
+
+/// A dump being received: the packets relayed from the ant in response to a single AntStart.
+#[derive(Debug, Clone)]
+pub struct Dump {
+    trigger:      UniqueId,
+    seen:         BTreeSet<(u32, u8)>,
+    first:        Option<MissionEpoch>,
+    latest:       MissionEpoch,
+    last_seq:     u8,
+    fram_start:   u16,
+    fram_end:     u16,
+    duplicates:   u32,
+    missing:      u32,
+    out_of_order: u32,
+}
+
+impl Dump {
+    pub fn new(trigger: UniqueId) -> Self {
+        Self {
+            trigger,
+            seen: BTreeSet::new(),
+            first: None,
+            latest: MissionEpoch::from(0u32),
+            last_seq: 0,
+            fram_start: 0,
+            fram_end: 0,
+            duplicates: 0,
+            missing: 0,
+            out_of_order: 0,
+        }
+    }
+
+    #[inline]
+    pub fn trigger(&self) -> UniqueId {
+        self.trigger
+    }
+
+    /// Account for a packet with the given header timestamp and sequence number. Returns whether it
+    /// was new.
+    pub fn insert(&mut self, timestamp: MissionEpoch, seq: u8, fram_usage: u16) -> bool {
+        if !self.seen.insert((u32::from(timestamp), seq)) {
+            self.duplicates += 1;
+            return false;
+        }
+
+        let Some(first) = self.first else {
+            self.first = Some(timestamp);
+            self.latest = timestamp;
+            self.last_seq = seq;
+            self.fram_start = fram_usage;
+            self.fram_end = fram_usage;
+
+            return true;
+        };
+
+        if ms_between(first, timestamp) < 0 {
+            self.first = Some(timestamp);
+        }
+
+        let skipped = seq.wrapping_sub(self.last_seq.wrapping_add(1));
+
+        // more than half the sequence space ahead is taken to be behind: a packet we'd counted as
+        // missing, arriving late
+        if skipped >= 0x80 {
+            self.out_of_order += 1;
+            self.missing = self.missing.saturating_sub(1);
+
+            return true;
+        }
+
+        if ms_between(self.latest, timestamp) < 0 {
+            // in sequence, but timestamped before the previous packet
+            self.out_of_order += 1;
+        } else {
+            self.latest = timestamp;
+        }
+
+        self.missing += skipped as u32;
+        self.last_seq = seq;
+        self.fram_end = fram_usage;
+
+        true
+    }
+
+    pub fn stats(&self) -> Stats {
+        let duration = self.first.map_or(0, |first| ms_between(first, self.latest).max(0) as u64);
+
+        Stats {
+            trigger:      self.trigger,
+            packets:      self.seen.len() as u32,
+            duplicates:   self.duplicates,
+            missing:      self.missing,
+            out_of_order: self.out_of_order,
+            duration:     Duration::from_millis(duration),
+            fram_drained: self.fram_start.saturating_sub(self.fram_end) as u32,
+        }
+    }
+}
+
+/// Groups relayed ant packets into dumps by the AntStart they answer, and reports each dump's
+/// statistics once it goes quiet.
+pub struct Dumps {
+    config:         Config,
+    subscribe_once: Once,
+    current:        Option<Dump>,
+    quiet_timer:    Option<SpawnHandle>,
+}
+
+impl Default for Dumps {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl Dumps {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            current: None,
+            quiet_timer: None,
+        }
+    }
+
+    #[inline]
+    fn report(&self, report: Report) {
+        self.issue_system_async(ground::DumpReport(report));
+    }
+
+    fn complete(&mut self, ctx: &mut Context<Self>) {
+        if let Some(handle) = self.quiet_timer.take() {
+            ctx.cancel_future(handle);
+        }
+
+        let Some(dump) = self.current.take() else {
+            return;
+        };
+
+        let stats = dump.stats();
+        tracing::info!(%stats, "dump complete");
+
+        self.report(Report::Complete(stats));
+        self.issue_system_async(DumpComplete(stats));
+    }
+}
+
+impl Actor for Dumps {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, serial::AntMessage>(ctx);
+        });
+
+        // the quiet timer belonged to the previous context: report what we have rather than
+        // leaving the dump open
+        if self.quiet_timer.take().is_some() {
+            self.complete(ctx);
+        }
+
+        tracing::info!("dump tracking started");
+    }
+}
+
+impl Supervised for Dumps {}
+
+impl Handler<serial::AntMessage> for Dumps {
+    type Result = ();
+
+    fn handle(&mut self, msg: serial::AntMessage, ctx: &mut Self::Context) -> Self::Result {
+        let packet = msg.0;
+        let header = packet.header.header;
+
+        let SourceInfo::Info(source) = packet.header.payload else {
+            return;
+        };
+
+        if source.header.ty.event != Event::AntStart {
+            return;
+        }
+
+        let trigger = source.header.unique_id();
+
+        if self.current.as_ref().map_or(false, |dump| dump.trigger() != trigger) {
+            tracing::info!(%trigger, "new dump started before the last went quiet");
+            self.complete(ctx);
+        }
+
+        if self.current.is_none() {
+            tracing::info!(%trigger, "dump started");
+            self.report(Report::Started {
+                trigger,
+            });
+        }
+
+        let dump = self.current.get_or_insert_with(|| Dump::new(trigger));
+
+        if !dump.insert(header.timestamp, header.seq, packet.payload.fram_usage) {
+            tracing::debug!(unique_id = %header.unique_id(), "duplicate dump packet");
+        }
+
+        if let Some(handle) = self.quiet_timer.take() {
+            ctx.cancel_future(handle);
+        }
+
+        self.quiet_timer = Some(ctx.run_later(self.config.quiet, |act, ctx| act.complete(ctx)));
+    }
+}
@@ -1,6 +1,13 @@
 use std::{
     io,
-    sync::Arc,
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+    },
     time::Duration,
 };
 
@@ -9,16 +16,27 @@ use actix::{
     prelude::*,
 };
 use actix_broker::{
+    Broker,
     BrokerSubscribe,
     SystemBroker,
 };
 use futures::future::BoxFuture;
 
-use message::Downlink as DownlinkMsg;
+use message::{
+    payload::{
+        log::{
+            Header,
+            Type,
+        },
+        realtime_status::Flags,
+    },
+    Downlink as DownlinkMsg,
+};
 use net::DatagramSender;
 
 use crate::{
     ground,
+    housekeeping,
     serial,
 };
 
@@ -34,6 +52,42 @@ pub enum Error {
     Io(#[from] io::Error),
 }
 
+/// Time allowed for the shutdown lifecycle event to be downlinked before the process stops.
+pub const SHUTDOWN_GRACE: Duration = Duration::from_millis(500);
+
+/// Downlink messages serialized but not yet sent, across all downlink sockets.
+static BACKLOG: AtomicUsize = AtomicUsize::new(0);
+
+#[inline]
+pub fn backlog() -> usize {
+    BACKLOG.load(Ordering::Relaxed)
+}
+
+/// Lifecycle events raised before any downlink socket connected. `None` once one has, after which
+/// events are issued straight away.
+static EARLY_LIFECYCLE: Mutex<Option<Vec<Header>>> = Mutex::new(Some(Vec::new()));
+
+/// Downlink a lifecycle event, stamped now. Events raised before the first downlink socket connects
+/// are held and sent when it does.
+pub fn lifecycle(ty: Type) {
+    let header = Header::now(ty);
+    let mut early = EARLY_LIFECYCLE.lock().unwrap_or_else(|e| e.into_inner());
+
+    match *early {
+        Some(ref mut early) => early.push(header),
+        None => Broker::<SystemBroker>::issue_async(ground::LifecycleReport(header)),
+    }
+}
+
+/// Send any held lifecycle events, and stop holding them.
+fn flush_lifecycle() {
+    let early = EARLY_LIFECYCLE.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+    for header in early.into_iter().flatten() {
+        Broker::<SystemBroker>::issue_async(ground::LifecycleReport(header));
+    }
+}
+
 pub struct Downlink {
     make_socket:    Box<dyn Fn() -> BoxFuture<'static, Option<BoxSender>>>,
     sender:         Option<BoxSender>,
@@ -77,7 +131,24 @@ impl Actor for Downlink {
                 a.subscribe_async::<SystemBroker, ground::UpPacket>(ctx);
                 a.subscribe_async::<SystemBroker, ground::UpCommand>(ctx);
                 a.subscribe_async::<SystemBroker, ground::Log>(ctx);
+                a.subscribe_async::<SystemBroker, ground::StateMachineReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::AntDfuReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::CsDfuReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::LinkReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::AntOpReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::ScheduleReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::SequenceReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::LimitsReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::ThermostatReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::DumpReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::TelemetryReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::StatusReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::LifecycleReport>(ctx);
+                a.subscribe_async::<SystemBroker, ground::QueueReport>(ctx);
             });
+
+            flush_lifecycle();
+            lifecycle(Type::DownlinkConnected);
         });
 
         ctx.wait(run);
@@ -121,20 +192,26 @@ fn gen_handle<T>(
         Ok(result) => result,
         Err(e) => {
             tracing::error!(error = %e, ty = %name, "serializing downlink data");
+            housekeeping::raise(Flags::DOWNLINK_PACKET_ERROR);
             return;
         },
     };
 
     let sender = sender.unwrap().clone();
 
+    BACKLOG.fetch_add(1, Ordering::Relaxed);
+
     ctx.wait(
         fut::wrap_future(async move {
             let result = result;
             sender.send(&result).await
         })
         .map(|result, _a, ctx: &mut Context<Downlink>| {
+            BACKLOG.fetch_sub(1, Ordering::Relaxed);
+
             if let Err(e) = result {
                 tracing::error!(error = %e, "failed sending packet to downlink, reconnecting");
+                housekeeping::raise(Flags::SOCKET_ERROR);
 
                 ctx.stop();
             }
@@ -185,3 +262,29 @@ imp!(serial::DownMessage, |msg: &serial::DownMessage| {
 });
 imp!(ground::UpCommand, |msg: &ground::UpCommand| DownlinkMsg::UplinkInterpreted(msg.0.clone()));
 imp!(ground::Log, |msg: &ground::Log| DownlinkMsg::Log(msg.0.clone()));
+imp!(ground::StateMachineReport, |msg: &ground::StateMachineReport| {
+    DownlinkMsg::StateMachine(msg.0.clone())
+});
+imp!(ground::AntDfuReport, |msg: &ground::AntDfuReport| DownlinkMsg::AntDfu(msg.0.clone()));
+imp!(ground::CsDfuReport, |msg: &ground::CsDfuReport| DownlinkMsg::CsDfu(msg.0.clone()));
+imp!(ground::LinkReport, |msg: &ground::LinkReport| DownlinkMsg::Link(msg.0.clone()));
+imp!(ground::AntOpReport, |msg: &ground::AntOpReport| DownlinkMsg::AntOp(msg.0.clone()));
+imp!(ground::ScheduleReport, |msg: &ground::ScheduleReport| {
+    DownlinkMsg::Schedule(msg.0.clone())
+});
+imp!(ground::SequenceReport, |msg: &ground::SequenceReport| {
+    DownlinkMsg::Sequence(msg.0.clone())
+});
+imp!(ground::LimitsReport, |msg: &ground::LimitsReport| DownlinkMsg::Limits(msg.0.clone()));
+imp!(ground::ThermostatReport, |msg: &ground::ThermostatReport| {
+    DownlinkMsg::Thermostat(msg.0.clone())
+});
+imp!(ground::DumpReport, |msg: &ground::DumpReport| DownlinkMsg::Dump(msg.0.clone()));
+imp!(ground::TelemetryReport, |msg: &ground::TelemetryReport| {
+    DownlinkMsg::Telemetry(msg.0.clone())
+});
+imp!(ground::StatusReport, |msg: &ground::StatusReport| DownlinkMsg::Status(msg.0.clone()));
+imp!(ground::LifecycleReport, |msg: &ground::LifecycleReport| {
+    DownlinkMsg::Lifecycle((&msg.0).into())
+});
+imp!(ground::QueueReport, |msg: &ground::QueueReport| DownlinkMsg::Queue(msg.0.clone()));
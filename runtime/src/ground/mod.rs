@@ -19,3 +19,59 @@ pub struct UpCommand(pub message::Message);
 #[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
 #[rtype(result = "()")]
 pub struct Log(pub message::downlink::log::Log);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct StateMachineReport(pub message::downlink::state_machine::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct AntDfuReport(pub message::downlink::dfu::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct CsDfuReport(pub message::downlink::dfu::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct LinkReport(pub message::downlink::link::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct AntOpReport(pub message::downlink::ant_op::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct ScheduleReport(pub message::downlink::schedule::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct SequenceReport(pub message::downlink::sequence::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct LimitsReport(pub message::downlink::limits::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct ThermostatReport(pub message::downlink::thermostat::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct DumpReport(pub message::downlink::dump::Report);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct TelemetryReport(pub message::downlink::telemetry::Telemetry);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct StatusReport(pub message::payload::RealtimeStatus);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct LifecycleReport(pub message::payload::log::Header);
+
+#[derive(Clone, Debug, PartialEq, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct QueueReport(pub message::downlink::queue::Report);
@@ -1,16 +1,38 @@
-use std::io;
+use std::{
+    io,
+    sync::Once,
+    time::{
+        Duration,
+        Instant,
+    },
+};
 
 use actix::prelude::*;
-use actix_broker::BrokerIssue;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
 use bytes::BytesMut;
 use futures::{
     future::BoxFuture,
     prelude::*,
 };
-use message::BytesWrap;
+use message::{
+    downlink::link::Report,
+    payload::{
+        log::Type,
+        realtime_status::Flags,
+    },
+    BytesWrap,
+};
 use packed_struct::PackedStructSlice;
 
-use crate::ground;
+use crate::{
+    config,
+    ground,
+    housekeeping,
+};
 
 type StaticReceiver = dyn net::DatagramReceiver + 'static + Unpin + Send + Sync;
 
@@ -39,6 +61,7 @@ impl Actor for Uplink {
                 };
 
                 tracing::info!("connected to uplink socket");
+                ground::downlink::lifecycle(Type::UplinkConnected);
 
                 let packets =
                     stream::try_unfold((receiver, BytesMut::new()), |(recv, mut buf)| async move {
@@ -73,6 +96,7 @@ where
             Ok(pkt) => pkt,
             Err(e) => {
                 tracing::error!(error = %e, "receiving packet");
+                housekeeping::raise(Flags::SOCKET_ERROR);
                 ctx.stop();
                 return;
             },
@@ -89,6 +113,7 @@ where
             Ok(msg) => msg,
             Err(e) => {
                 tracing::error!(error = %e, "bad uplink message format");
+                housekeeping::raise(Flags::UPLINK_PACKET_ERROR);
                 return;
             },
         };
@@ -97,3 +122,159 @@ where
         self.issue_system_async(ground::UpCommand(msg));
     }
 }
+
+/// Whether the rover is currently uplinking, as judged by [`Heartbeat`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Message)]
+#[rtype(result = "()")]
+pub struct Liveness {
+    pub alive: bool,
+}
+
+/// Rover uplink liveness parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// On by default. Disable (or raise `timeout`) if the rover may legitimately stop uplinking
+    /// for longer than `timeout` while the ant is running: the ant would be stopped.
+    pub enabled: bool,
+
+    /// The rover is considered gone if nothing is uplinked for this long.
+    #[serde(with = "crate::serde_millis")]
+    pub timeout: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            timeout: Duration::from_secs(120),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if self.timeout.is_zero() {
+            return Err(config::Error {
+                name:   "uplink.timeout",
+                reason: "must be nonzero",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            enabled = self.enabled,
+            timeout = ?self.timeout,
+            "uplink heartbeat config"
+        );
+    }
+}
+
+/// Watches for uplink packets from the rover. If none arrive within the configured window, raises
+/// `FEUplinkLost` (so the state machine can safe the ant) and downlinks an alarm; raises
+/// `FEUplinkResumed` when the rover is heard from again.
+pub struct Heartbeat {
+    config:         Config,
+    subscribe_once: Once,
+    last_seen:      Instant,
+    /// Whether the rover has been declared silent.
+    silent:         bool,
+    timer:          Option<SpawnHandle>,
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl Heartbeat {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            last_seen: Instant::now(),
+            silent: false,
+            timer: None,
+        }
+    }
+
+    fn arm(&mut self, ctx: &mut Context<Self>) {
+        if let Some(handle) = self.timer.take() {
+            ctx.cancel_future(handle);
+        }
+
+        self.timer = Some(ctx.run_later(self.config.timeout, |a, _ctx| {
+            a.timer = None;
+            a.expired();
+        }));
+    }
+
+    fn expired(&mut self) {
+        let silent_for = self.last_seen.elapsed();
+
+        tracing::error!(?silent_for, "no uplink from rover, safing");
+
+        self.silent = true;
+        self.issue_system_async(Liveness {
+            alive: false,
+        });
+        self.issue_system_async(ground::LinkReport(Report::RoverSilent {
+            silent_for,
+        }));
+    }
+}
+
+impl Actor for Heartbeat {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if !self.config.enabled {
+            tracing::info!("uplink heartbeat disabled");
+            return;
+        }
+
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, ground::UpPacket>(ctx);
+        });
+
+        // any timer belonged to the previous context
+        self.timer = None;
+
+        if !self.silent {
+            self.arm(ctx);
+        }
+
+        tracing::info!("uplink heartbeat started");
+    }
+}
+
+impl Supervised for Heartbeat {}
+
+impl Handler<ground::UpPacket> for Heartbeat {
+    type Result = ();
+
+    fn handle(&mut self, _msg: ground::UpPacket, ctx: &mut Self::Context) -> Self::Result {
+        if self.silent {
+            let down_for = self.last_seen.elapsed();
+
+            tracing::info!(?down_for, "rover uplink resumed");
+
+            self.silent = false;
+            self.issue_system_async(Liveness {
+                alive: true,
+            });
+            self.issue_system_async(ground::LinkReport(Report::RoverRecovered {
+                down_for,
+            }));
+        }
+
+        self.last_seen = Instant::now();
+        self.arm(ctx);
+    }
+}
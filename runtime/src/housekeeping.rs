@@ -0,0 +1,168 @@
+use std::{
+    sync::Once,
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    Broker,
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+
+use message::payload::{
+    realtime_status::Flags,
+    RealtimeStatus,
+};
+
+use crate::{
+    config,
+    ground,
+};
+
+/// Raise status flags, to be reported in the next status frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Message)]
+#[rtype(result = "()")]
+pub struct Raise(pub Flags);
+
+/// Raise `flags` from outside an actor.
+#[inline]
+pub fn raise(flags: Flags) {
+    Broker::<SystemBroker>::issue_async(Raise(flags));
+}
+
+/// Relay health reporting parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Time between status frames. A frame is also sent as soon as a new flag is raised.
+    #[serde(with = "crate::serde_millis")]
+    pub interval: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if self.interval.is_zero() {
+            return Err(config::Error {
+                name:   "housekeeping.interval",
+                reason: "must be nonzero",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(interval = ?self.interval, "housekeeping config");
+    }
+}
+
+/// Resident memory of this process, where we know how to find it.
+#[cfg(target_os = "linux")]
+fn memory_mb() -> Option<f64> {
+    // see proc(5): VmRSS is reported in kB, whatever the page size
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let rss = status.lines().find_map(|line| line.strip_prefix("VmRSS:"))?;
+    let kb = rss.trim().trim_end_matches("kB").trim_end().parse::<f64>().ok()?;
+
+    Some(kb / 1024.)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn memory_mb() -> Option<f64> {
+    None
+}
+
+/// Periodically downlinks a [`RealtimeStatus`] frame: memory usage, downlink backlog, and any
+/// error flags raised since the last frame.
+pub struct Housekeeping {
+    config:         Config,
+    subscribe_once: Once,
+    /// Flags raised since the last frame.
+    flags:          Flags,
+    /// Flags in the last frame.
+    last:           Flags,
+    booted:         bool,
+}
+
+impl Default for Housekeeping {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl Housekeeping {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            flags: Flags::NONE,
+            last: Flags::NONE,
+            booted: false,
+        }
+    }
+
+    fn report(&mut self) {
+        let mut flags = std::mem::take(&mut self.flags);
+        self.last = flags;
+
+        if !self.booted {
+            flags |= Flags::BOOTED;
+            self.booted = true;
+        }
+
+        let status = RealtimeStatus {
+            memory_usage: memory_mb().map_or(0, RealtimeStatus::scale_memory),
+            logs_pending: ground::downlink::backlog().try_into().unwrap_or(u8::MAX),
+            flags,
+        };
+
+        tracing::debug!(%status, "relay status");
+        self.issue_system_async(ground::StatusReport(status));
+    }
+}
+
+impl Actor for Housekeeping {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, Raise>(ctx);
+        });
+
+        self.report();
+        ctx.run_interval(self.config.interval, |a, _ctx| a.report());
+
+        tracing::info!("housekeeping started");
+    }
+}
+
+impl Supervised for Housekeeping {}
+
+impl Handler<Raise> for Housekeeping {
+    type Result = ();
+
+    fn handle(&mut self, Raise(flags): Raise, _ctx: &mut Self::Context) -> Self::Result {
+        // still set from the last frame or already pending: not a change worth reporting early
+        let known = self.flags | self.last;
+        self.flags |= flags;
+
+        if known.contains(flags) {
+            return;
+        }
+
+        tracing::debug!(%flags, "status flags raised");
+        self.report();
+    }
+}
@@ -0,0 +1,63 @@
+use std::{
+    fs,
+    io,
+    io::Write,
+    marker::PhantomData,
+    path::PathBuf,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Format(#[from] serde_json::Error),
+}
+
+/// Single-entry on-disk journal. Every write replaces the previous entry atomically (write to a
+/// temporary file, sync, rename), so a crash mid-write leaves the last good entry in place.
+#[derive(Debug, Clone)]
+pub struct Journal<T> {
+    path:   PathBuf,
+    _entry: PhantomData<T>,
+}
+
+impl<T> Journal<T>
+where
+    T: std::fmt::Debug + serde::Serialize + serde::de::DeserializeOwned,
+{
+    #[inline]
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path:   path.into(),
+            _entry: PhantomData,
+        }
+    }
+
+    #[tracing::instrument(skip(self), fields(path = %self.path.display()), err(Display))]
+    pub fn load(&self) -> Result<Option<T>, Error> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Some(serde_json::from_slice(&data)?))
+    }
+
+    #[tracing::instrument(skip(self), fields(path = %self.path.display()), err(Display))]
+    pub fn store(&self, entry: &T) -> Result<(), Error> {
+        let tmp = self.path.with_extension("tmp");
+
+        {
+            let mut f = fs::File::create(&tmp)?;
+            f.write_all(&serde_json::to_vec(entry)?)?;
+            f.sync_all()?;
+        }
+
+        fs::rename(&tmp, &self.path)?;
+
+        Ok(())
+    }
+}
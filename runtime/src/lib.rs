@@ -1,12 +1,28 @@
 #![feature(try_blocks)]
 #![feature(duration_constants)]
 
+pub mod config;
+pub mod dfu;
+pub mod dump;
 pub mod ground;
+pub mod housekeeping;
+mod journal;
+pub mod limits;
+pub mod scheduler;
+pub mod sequencer;
+mod serde_millis;
 pub mod serial;
 mod state_machine;
 pub mod system;
+pub mod thermostat;
 
-pub use state_machine::StateMachine;
+pub use state_machine::{
+    table,
+    timing,
+    RunOp,
+    State,
+    StateMachine,
+};
 
 pub use system::{
     params,
@@ -0,0 +1,397 @@
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    sync::Once,
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+
+use message::{
+    downlink::limits::{
+        Field,
+        Level,
+        Report,
+    },
+    header::{
+        Destination,
+        Event,
+    },
+};
+
+use crate::{
+    config,
+    ground,
+    params,
+    serial,
+    table::{
+        Command,
+        Retry,
+        Table,
+    },
+    RunOp,
+};
+
+/// A limit has been violated badly enough that a running ant should be stopped. Raised as
+/// `FELimitTrip` at the state machine.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Message)]
+#[rtype(result = "()")]
+pub struct Trip {
+    pub field: Field,
+}
+
+/// Range of acceptable values. Values below `low` or above `high` violate the band.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Band {
+    pub low:  Option<u32>,
+    pub high: Option<u32>,
+}
+
+impl Band {
+    /// Whether `value` lies outside the band once it's been narrowed by `margin` on each side.
+    #[inline]
+    fn violated(&self, value: u32, margin: u32) -> bool {
+        self.low.map_or(false, |low| value < low.saturating_add(margin))
+            || self.high.map_or(false, |high| value > high.saturating_sub(margin))
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        matches!((self.low, self.high), (Some(low), Some(high)) if low > high)
+    }
+
+    /// Whether this band's thresholds lie within `other`'s, on the sides where both are set.
+    #[inline]
+    fn within(&self, other: &Band) -> bool {
+        let below = matches!((self.low, other.low), (Some(low), Some(other)) if low < other);
+        let above = matches!((self.high, other.high), (Some(high), Some(other)) if high > other);
+
+        !below && !above
+    }
+}
+
+/// Something to do automatically when a field reaches a level.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum Response {
+    /// Stop the ant if it's running.
+    StopAnt,
+
+    /// Send a command. Ant ops are handed to the state machine like any other op; other commands
+    /// are retried until they're acked.
+    Send {
+        command: Command,
+    },
+}
+
+/// Yellow and red thresholds for a single telemetry field.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Limit {
+    pub field: Field,
+
+    #[serde(default)]
+    pub yellow: Band,
+
+    #[serde(default)]
+    pub red: Band,
+
+    /// How far back inside a band a value must come before its level drops, so that a value
+    /// hovering at a threshold doesn't flap.
+    #[serde(default)]
+    pub hysteresis: u32,
+
+    /// Responses to the field reaching each level. Only the new level's responses run, and only
+    /// when the level changes.
+    #[serde(default)]
+    pub on_nominal: Vec<Response>,
+    #[serde(default)]
+    pub on_yellow:  Vec<Response>,
+    #[serde(default)]
+    pub on_red:     Vec<Response>,
+}
+
+impl Limit {
+    fn classify(&self, value: u32, margin: u32) -> Level {
+        if self.red.violated(value, margin) {
+            Level::Red
+        } else if self.yellow.violated(value, margin) {
+            Level::Yellow
+        } else {
+            Level::Nominal
+        }
+    }
+
+    /// Level of the field on reading `value`, given that it was previously at `current`. Levels
+    /// rise as soon as a threshold is crossed, but only fall once the value is clear of the
+    /// threshold by `hysteresis`.
+    pub fn next_level(&self, current: Level, value: u32) -> Level {
+        let level = self.classify(value, 0);
+
+        if level >= current {
+            return level;
+        }
+
+        self.classify(value, self.hysteresis).min(current)
+    }
+
+    #[inline]
+    pub fn responses(&self, level: Level) -> &[Response] {
+        match level {
+            Level::Nominal => &self.on_nominal,
+            Level::Yellow => &self.on_yellow,
+            Level::Red => &self.on_red,
+        }
+    }
+}
+
+/// Value of `field` in a telemetry sample.
+pub fn read(field: Field, telemetry: &serial::Telemetry) -> u32 {
+    let serial::Telemetry {
+        cs,
+        ant,
+    } = telemetry;
+
+    match field {
+        Field::PcbTemp => ant.pcb_temp as u32,
+        Field::BatteryTemp => ant.battery_temp as u32,
+        Field::BatteryVoltage => ant.battery_voltage as u32,
+        Field::FramUsage => ant.fram_usage as u32,
+        Field::CsTemperature => cs.temperature as u32,
+        Field::Power5v => cs.power_5v as u32,
+        Field::PowerVcc => cs.power_vcc as u32,
+        Field::FramUsed => cs.fram_used as u32,
+    }
+}
+
+/// Telemetry limit parameters. No limits are checked by default: thresholds are in raw telemetry
+/// units and depend on the hardware.
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub limits: Vec<Limit>,
+
+    /// Retry parameters for commands sent in response to a limit that aren't ant ops.
+    pub retry: Retry,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            limits: vec![],
+            retry:  Retry {
+                timeout:   Duration::from_secs(2),
+                base:      Duration::from_millis(500),
+                max_delay: Duration::from_secs(5),
+                count:     5,
+            },
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        let mut fields = BTreeSet::new();
+
+        for limit in &self.limits {
+            if !fields.insert(limit.field) {
+                return Err(config::Error {
+                    name:   "limits.limits",
+                    reason: "each field may only have one limit",
+                });
+            }
+
+            if limit.yellow.is_empty() || limit.red.is_empty() {
+                return Err(config::Error {
+                    name:   "limits.limits",
+                    reason: "band low must not exceed high",
+                });
+            }
+
+            if !limit.yellow.within(&limit.red) {
+                return Err(config::Error {
+                    name:   "limits.limits",
+                    reason: "yellow band must lie within red band",
+                });
+            }
+
+            let mut commands = [&limit.on_nominal, &limit.on_yellow, &limit.on_red]
+                .into_iter()
+                .flatten()
+                .filter_map(|response| match response {
+                    Response::Send {
+                        command,
+                    } => Some(command),
+                    Response::StopAnt => None,
+                });
+
+            if commands.any(|command| {
+                !matches!(command.destination, Destination::Ant | Destination::CentralStation)
+            }) {
+                return Err(config::Error {
+                    name:   "limits.limits",
+                    reason: "responses may only command the ant or the central station",
+                });
+            }
+        }
+
+        self.retry.validate("limits.retry")
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        let fields = self.limits.iter().map(|limit| limit.field.name()).collect::<Vec<_>>();
+
+        tracing::info!(?fields, retry = ?self.retry, "telemetry limits config");
+    }
+}
+
+/// Checks relayed telemetry against configured limits, downlinks level changes and runs the
+/// configured responses, so the ant is protected even with the ground out of the loop.
+pub struct Limits {
+    config:         Config,
+    subscribe_once: Once,
+    levels:         BTreeMap<Field, Level>,
+    /// Events that are ant ops, which are handed to the state machine rather than sent directly.
+    ops:            BTreeSet<Event>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self::new(Config::default(), Table::default().ops.iter().map(|op| op.event))
+    }
+}
+
+impl Limits {
+    /// Construct a limits engine that routes the ant ops `ops` through the state machine.
+    pub fn new(config: Config, ops: impl IntoIterator<Item = Event>) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            levels: BTreeMap::new(),
+            ops: ops.into_iter().collect(),
+        }
+    }
+
+    #[inline]
+    fn report(&self, report: Report) {
+        self.issue_system_async(ground::LimitsReport(report));
+    }
+
+    fn respond(&self, field: Field, level: Level, response: Response, ctx: &mut Context<Self>) {
+        let command = match response {
+            Response::StopAnt => {
+                tracing::warn!(%field, "limit response: stopping ant");
+                return self.issue_system_async(Trip {
+                    field,
+                });
+            },
+            Response::Send {
+                command,
+            } => command,
+        };
+
+        if self.ops.contains(&command.event) {
+            tracing::info!(%field, %command, "limit response: dispatching ant op");
+
+            let fut = fut::wrap_future(params()).map(move |params, act: &mut Self, _ctx| {
+                let msg = message::command(&params, command.destination, command.event);
+                act.issue_system_async(RunOp(msg));
+            });
+
+            ctx.spawn(fut);
+            return;
+        }
+
+        tracing::info!(%field, %command, "limit response: sending command");
+
+        let retry = self.config.retry;
+
+        let fut = fut::wrap_future(serial::send_retry(
+            move || {
+                Box::pin(async move {
+                    message::command(&params().await, command.destination, command.event)
+                })
+            },
+            retry.timeout,
+            retry.strategy(),
+        ))
+        .map(move |result, act: &mut Self, _ctx| {
+            if let Err(e) = result {
+                tracing::error!(error = %e, %field, %command, "limit response failed");
+
+                act.report(Report::ResponseFailed {
+                    field,
+                    level,
+                    destination: command.destination,
+                    event: command.event,
+                    error: e.to_string(),
+                });
+            }
+        });
+
+        ctx.spawn(fut);
+    }
+}
+
+impl Actor for Limits {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, serial::Telemetry>(ctx);
+        });
+
+        tracing::info!("telemetry limits started");
+    }
+}
+
+impl Supervised for Limits {
+    fn restarting(&mut self, _ctx: &mut <Self as Actor>::Context) {
+        tracing::warn!("telemetry limits restarting");
+    }
+}
+
+impl Handler<serial::Telemetry> for Limits {
+    type Result = ();
+
+    fn handle(&mut self, msg: serial::Telemetry, ctx: &mut Self::Context) -> Self::Result {
+        for limit in &self.config.limits {
+            let field = limit.field;
+            let value = read(field, &msg);
+
+            let from = self.levels.get(&field).copied().unwrap_or_default();
+            let to = limit.next_level(from, value);
+
+            if to == from {
+                continue;
+            }
+
+            match to {
+                Level::Nominal => tracing::info!(%field, value, "telemetry back within limits"),
+                Level::Yellow => tracing::warn!(%field, value, %from, "telemetry yellow limit"),
+                Level::Red => tracing::error!(%field, value, %from, "telemetry red limit"),
+            }
+
+            self.levels.insert(field, to);
+            self.report(Report::Changed {
+                field,
+                from,
+                to,
+                value,
+            });
+
+            for &response in limit.responses(to) {
+                self.respond(field, to, response, ctx);
+            }
+        }
+    }
+}
@@ -0,0 +1,376 @@
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    sync::Once,
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+use bytes::Bytes;
+
+use message::{
+    downlink::schedule::{
+        Entry,
+        Report,
+    },
+    header::{
+        Destination,
+        Event,
+    },
+    schedule::{
+        Command,
+        When,
+    },
+    MissionEpoch,
+};
+
+use crate::{
+    config,
+    ground,
+    params,
+    serial,
+    table::{
+        Retry,
+        Table,
+    },
+    RunOp,
+};
+
+/// Command scheduler parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Most commands that may be pending at once.
+    pub max_pending: usize,
+
+    /// Retry parameters for sending each command when it comes due.
+    pub retry: Retry,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_pending: 64,
+            retry:       Retry {
+                timeout:   Duration::from_secs(5),
+                base:      Duration::from_secs(1),
+                max_delay: Duration::from_secs(5),
+                count:     3,
+            },
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if self.max_pending == 0 {
+            return Err(config::Error {
+                name:   "scheduler.max_pending",
+                reason: "must be nonzero",
+            });
+        }
+
+        self.retry.validate("scheduler.retry")
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            max_pending = self.max_pending,
+            retry = ?self.retry,
+            "scheduler config"
+        );
+    }
+}
+
+/// Furthest ahead a command may be scheduled. `MissionEpoch` wraps, so times more than half its
+/// range away from now are ambiguous.
+const MAX_LEAD_MS: u32 = i32::MAX as u32;
+
+/// Time from now until `at`, or `None` if it's already passed.
+#[inline]
+fn until(at: MissionEpoch) -> Option<Duration> {
+    let ms = u32::from(at).wrapping_sub(u32::from(MissionEpoch::now()));
+
+    (ms <= MAX_LEAD_MS).then(|| Duration::from_millis(ms as u64))
+}
+
+/// When a command scheduled at `now` for `when` should be sent, or `None` if `when` is too far out
+/// to be represented.
+pub fn resolve(when: When, now: MissionEpoch) -> Option<MissionEpoch> {
+    match when {
+        When::At(at) => Some(at),
+        When::After(delay) => match u32::try_from(delay.as_millis()) {
+            Ok(ms) if ms <= MAX_LEAD_MS => {
+                Some(MissionEpoch::from(u32::from(now).wrapping_add(ms)))
+            },
+            _ => None,
+        },
+    }
+}
+
+struct Pending {
+    entry:   Entry,
+    payload: Bytes,
+    timer:   Option<SpawnHandle>,
+}
+
+/// Holds time-tagged commands uplinked from the ground (`FESchedule`) and sends them to the ant or
+/// the central station when they come due.
+pub struct Scheduler {
+    config:         Config,
+    subscribe_once: Once,
+    pending:        BTreeMap<u32, Pending>,
+    next_id:        u32,
+    /// Events that are ant ops, which are handed to the state machine when they come due rather
+    /// than sent directly.
+    ops:            BTreeSet<Event>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new(Config::default(), Table::default().ops.iter().map(|op| op.event))
+    }
+}
+
+impl Scheduler {
+    /// Construct a scheduler that routes the ant ops `ops` through the state machine.
+    pub fn new(config: Config, ops: impl IntoIterator<Item = Event>) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            pending: BTreeMap::new(),
+            next_id: 0,
+            ops: ops.into_iter().collect(),
+        }
+    }
+
+    #[inline]
+    fn report(&self, report: Report) {
+        self.issue_system_async(ground::ScheduleReport(report));
+    }
+
+    fn reject(&self, reason: impl ToString) {
+        let reason = reason.to_string();
+
+        tracing::warn!(%reason, "rejecting schedule command");
+        self.report(Report::Rejected(reason));
+    }
+
+    fn arm(&mut self, id: u32, ctx: &mut Context<Self>) {
+        let Some(pending) = self.pending.get_mut(&id) else {
+            return;
+        };
+
+        // anything that came due while we weren't running goes out immediately
+        let delay = until(pending.entry.at).unwrap_or_default();
+
+        pending.timer = Some(ctx.run_later(delay, move |a, ctx| a.fire(id, ctx)));
+    }
+
+    #[tracing::instrument(skip(self, payload, ctx), fields(len = payload.len()))]
+    fn add(
+        &mut self,
+        when: When,
+        destination: Destination,
+        event: Event,
+        payload: Bytes,
+        ctx: &mut Context<Self>,
+    ) {
+        if !matches!(destination, Destination::Ant | Destination::CentralStation) {
+            return self.reject(format!("can't schedule commands for {destination:?}"));
+        }
+
+        if self.pending.len() >= self.config.max_pending {
+            return self.reject(format!("{} commands already pending", self.pending.len()));
+        }
+
+        let Some(at) = resolve(when, MissionEpoch::now()) else {
+            return self.reject(format!("{when:?} is too far out"));
+        };
+
+        if until(at).is_none() {
+            return self.reject("execution time has already passed");
+        }
+
+        self.next_id = self.next_id.wrapping_add(1);
+        let id = self.next_id;
+
+        let entry = Entry {
+            id,
+            at,
+            destination,
+            event,
+        };
+
+        tracing::info!(%entry, "command scheduled");
+
+        self.pending.insert(id, Pending {
+            entry,
+            payload,
+            timer: None,
+        });
+
+        self.arm(id, ctx);
+        self.report(Report::Scheduled(entry));
+    }
+
+    fn cancel(&mut self, id: u32, ctx: &mut Context<Self>) {
+        let Some(pending) = self.pending.remove(&id) else {
+            return self.reject(format!("no pending command #{id}"));
+        };
+
+        if let Some(timer) = pending.timer {
+            ctx.cancel_future(timer);
+        }
+
+        tracing::info!(entry = %pending.entry, "scheduled command cancelled");
+        self.report(Report::Cancelled(pending.entry));
+    }
+
+    fn clear(&mut self, ctx: &mut Context<Self>) {
+        let count = self.pending.len();
+
+        for (_, pending) in std::mem::take(&mut self.pending) {
+            if let Some(timer) = pending.timer {
+                ctx.cancel_future(timer);
+            }
+        }
+
+        tracing::info!(count, "schedule cleared");
+        self.report(Report::Cleared {
+            count,
+        });
+    }
+
+    #[tracing::instrument(skip(self, ctx))]
+    fn fire(&mut self, id: u32, ctx: &mut Context<Self>) {
+        let Some(Pending {
+            entry,
+            payload,
+            ..
+        }) = self.pending.remove(&id)
+        else {
+            return;
+        };
+
+        if self.ops.contains(&entry.event) {
+            tracing::info!(%entry, "dispatching scheduled ant op");
+
+            let fut = fut::wrap_future(params()).map(move |params, act: &mut Self, _ctx| {
+                let msg =
+                    message::command_with_payload(&params, entry.destination, entry.event, payload);
+
+                act.issue_system_async(RunOp(msg));
+                act.report(Report::Dispatched(entry));
+            });
+
+            ctx.spawn(fut);
+            return;
+        }
+
+        tracing::info!(%entry, "sending scheduled command");
+
+        let retry = self.config.retry;
+
+        let fut = fut::wrap_future(serial::send_retry(
+            move || {
+                let payload = payload.clone();
+
+                Box::pin(async move {
+                    message::command_with_payload(
+                        &params().await,
+                        entry.destination,
+                        entry.event,
+                        payload,
+                    )
+                })
+            },
+            retry.timeout,
+            retry.strategy(),
+        ))
+        .map(move |result, act: &mut Self, _ctx| match result {
+            Ok(_) => act.report(Report::Acked(entry)),
+            Err(e) => {
+                tracing::error!(error = %e, %entry, "scheduled command failed");
+
+                act.report(Report::Failed {
+                    entry,
+                    error: e.to_string(),
+                });
+            },
+        });
+
+        ctx.spawn(fut);
+    }
+}
+
+impl Actor for Scheduler {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, ground::UpCommand>(ctx);
+        });
+
+        // timers don't survive a restart
+        let ids = self.pending.keys().copied().collect::<Vec<_>>();
+
+        if !ids.is_empty() {
+            tracing::warn!(count = ids.len(), "re-arming scheduled commands after restart");
+        }
+
+        for id in ids {
+            self.arm(id, ctx);
+        }
+
+        tracing::info!("scheduler started");
+    }
+}
+
+impl Supervised for Scheduler {
+    fn restarting(&mut self, _ctx: &mut <Self as Actor>::Context) {
+        tracing::warn!("scheduler restarting");
+    }
+}
+
+impl Handler<ground::UpCommand> for Scheduler {
+    type Result = ();
+
+    #[tracing::instrument(skip_all)]
+    fn handle(&mut self, ground::UpCommand(msg): ground::UpCommand, ctx: &mut Self::Context) {
+        if msg.header.header.ty.event != Event::FESchedule {
+            return;
+        }
+
+        let cmd = match Command::unpack_from_slice(msg.payload.as_ref()) {
+            Ok(cmd) => cmd,
+            Err(e) => return self.reject(format!("bad command payload: {e}")),
+        };
+
+        match cmd {
+            Command::Add {
+                when,
+                destination,
+                event,
+                payload,
+            } => self.add(when, destination, event, payload, ctx),
+            Command::List => {
+                let entries = self.pending.values().map(|p| p.entry).collect();
+                self.report(Report::Pending(entries));
+            },
+            Command::Cancel {
+                id,
+            } => self.cancel(id, ctx),
+            Command::Clear => self.clear(ctx),
+        }
+    }
+}
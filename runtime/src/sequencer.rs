@@ -0,0 +1,462 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::Once,
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+use futures::future::BoxFuture;
+
+use message::{
+    downlink::sequence::{
+        Report,
+        Summary,
+    },
+    header::{
+        Destination,
+        Disposition,
+        Event,
+    },
+    sequence::{
+        Command,
+        Step,
+        Wait,
+    },
+};
+
+use crate::{
+    config,
+    ground,
+    journal::Journal,
+    params,
+    serial,
+};
+
+/// Sequence storage limits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub max_sequences: usize,
+    pub max_steps:     usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            max_sequences: 32,
+            max_steps:     64,
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if self.max_sequences == 0 {
+            return Err(config::Error {
+                name:   "sequencer.max_sequences",
+                reason: "must be nonzero",
+            });
+        }
+
+        if self.max_steps == 0 {
+            return Err(config::Error {
+                name:   "sequencer.max_steps",
+                reason: "must be nonzero",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            max_sequences = self.max_sequences,
+            max_steps = self.max_steps,
+            "sequencer config"
+        );
+    }
+}
+
+/// Stored sequences by name.
+pub type Store = BTreeMap<String, Vec<Step>>;
+
+/// Why `step` can't be part of a sequence, if it can't.
+pub fn check_step(step: &Step) -> Result<(), &'static str> {
+    if let Some((dest, _)) = step.command {
+        if !matches!(dest, Destination::Ant | Destination::CentralStation) {
+            return Err("commands must be for the ant or the central station");
+        }
+    }
+
+    match (step.command, step.wait) {
+        (None, Wait::Ack) => Err("can't wait for an ack without a command"),
+        (Some(_), Wait::Nothing) => Ok(()),
+        _ if step.timeout.is_zero() => Err("timeout must be nonzero"),
+        _ => Ok(()),
+    }
+}
+
+fn command(dest: Destination, event: Event) -> BoxFuture<'static, message::Message> {
+    Box::pin(async move { message::command(&params().await, dest, event) })
+}
+
+/// A sequence in progress.
+struct Run {
+    name:        String,
+    steps:       Vec<Step>,
+    index:       usize,
+    /// Event the current step is waiting for.
+    waiting_for: Option<Event>,
+    /// Send and timeout futures belonging to the current step.
+    tasks:       Vec<SpawnHandle>,
+}
+
+/// Stores named command sequences uplinked from the ground (`FESequence`) and runs them on
+/// request, one at a time. A step that isn't acked, or whose event doesn't arrive, in time aborts
+/// the sequence.
+pub struct Sequencer {
+    config:         Config,
+    subscribe_once: Once,
+    sequences:      Store,
+    journal:        Option<Journal<Store>>,
+    run:            Option<Run>,
+}
+
+impl Default for Sequencer {
+    fn default() -> Self {
+        Self::new(None, Config::default())
+    }
+}
+
+impl Sequencer {
+    /// Construct a sequencer that persists stored sequences to `store_path` (if provided),
+    /// loading any that were stored there previously.
+    pub fn new(store_path: Option<PathBuf>, config: Config) -> Self {
+        let mut result = Self {
+            config,
+            subscribe_once: Once::new(),
+            sequences: Store::new(),
+            journal: None,
+            run: None,
+        };
+
+        let Some(journal) = store_path.map(Journal::new) else {
+            return result;
+        };
+
+        match journal.load() {
+            Ok(Some(sequences)) => {
+                tracing::info!(count = sequences.len(), "loaded stored sequences");
+                result.sequences = sequences;
+            },
+            Ok(None) => tracing::info!("no stored sequences"),
+            Err(e) => tracing::error!(error = %e, "loading stored sequences"),
+        }
+
+        result.journal = Some(journal);
+        result
+    }
+
+    #[inline]
+    fn report(&self, report: Report) {
+        self.issue_system_async(ground::SequenceReport(report));
+    }
+
+    fn reject(&self, reason: impl ToString) {
+        let reason = reason.to_string();
+
+        tracing::warn!(%reason, "rejecting sequence command");
+        self.report(Report::Rejected(reason));
+    }
+
+    fn persist(&self) {
+        let Some(ref journal) = self.journal else {
+            return;
+        };
+
+        if let Err(e) = journal.store(&self.sequences) {
+            tracing::error!(error = %e, "storing sequences");
+        }
+    }
+
+    #[tracing::instrument(skip(self, steps), fields(steps = steps.len()))]
+    fn define(&mut self, name: String, steps: Vec<Step>) {
+        if name.is_empty() {
+            return self.reject("sequence name must not be empty");
+        }
+
+        if steps.is_empty() || steps.len() > self.config.max_steps {
+            return self.reject(format!(
+                "sequences must have between 1 and {} steps",
+                self.config.max_steps
+            ));
+        }
+
+        if let Some((i, reason)) =
+            steps.iter().enumerate().find_map(|(i, step)| check_step(step).err().map(|e| (i, e)))
+        {
+            return self.reject(format!("step {i}: {reason}"));
+        }
+
+        if !self.sequences.contains_key(&name) && self.sequences.len() >= self.config.max_sequences
+        {
+            return self.reject(format!("{} sequences already stored", self.sequences.len()));
+        }
+
+        tracing::info!("sequence defined");
+
+        let summary = Summary {
+            name:  name.clone(),
+            steps: steps.len(),
+        };
+
+        self.sequences.insert(name, steps);
+        self.persist();
+
+        self.report(Report::Defined(summary));
+    }
+
+    fn delete(&mut self, name: String) {
+        if self.sequences.remove(&name).is_none() {
+            return self.reject(format!("no sequence {name:?}"));
+        }
+
+        self.persist();
+        self.report(Report::Deleted(name));
+    }
+
+    #[tracing::instrument(skip(self, ctx))]
+    fn start(&mut self, name: String, ctx: &mut Context<Self>) {
+        if let Some(ref run) = self.run {
+            return self.reject(format!("sequence {:?} already running", run.name));
+        }
+
+        let Some(steps) = self.sequences.get(&name).cloned() else {
+            return self.reject(format!("no sequence {name:?}"));
+        };
+
+        tracing::info!("starting sequence");
+
+        self.report(Report::Started(Summary {
+            name:  name.clone(),
+            steps: steps.len(),
+        }));
+
+        self.run = Some(Run {
+            name,
+            steps,
+            index: 0,
+            waiting_for: None,
+            tasks: vec![],
+        });
+
+        self.run_step(ctx);
+    }
+
+    /// Start the current step of the running sequence.
+    fn run_step(&mut self, ctx: &mut Context<Self>) {
+        let Some(ref mut run) = self.run else {
+            return;
+        };
+
+        let Some(&step) = run.steps.get(run.index) else {
+            tracing::info!(name = %run.name, "sequence completed");
+
+            let name = run.name.clone();
+            self.run = None;
+
+            return self.report(Report::Completed(name));
+        };
+
+        tracing::info!(name = %run.name, index = run.index, %step, "running sequence step");
+
+        let handle = match (step.command, step.wait) {
+            (Some((dest, event)), Wait::Ack) => {
+                let retries = std::iter::repeat(Duration::ZERO).take(step.retries as usize);
+
+                ctx.spawn(
+                    fut::wrap_future(serial::send_retry(
+                        move || command(dest, event),
+                        step.timeout,
+                        retries,
+                    ))
+                    .map(|result, act: &mut Self, ctx| match result {
+                        Ok(_) => act.advance(ctx),
+                        Err(e) => act.abort(format!("not acked: {e}"), ctx),
+                    }),
+                )
+            },
+
+            (Some((dest, event)), Wait::Nothing) => ctx.spawn(
+                fut::wrap_future(async move { serial::do_send(command(dest, event).await).await })
+                    .map(|(), act: &mut Self, ctx| act.advance(ctx)),
+            ),
+
+            (cmd, Wait::Event(event)) => {
+                run.waiting_for = Some(event);
+
+                if let Some((dest, cmd_event)) = cmd {
+                    let send = async move { serial::do_send(command(dest, cmd_event).await).await };
+                    run.tasks.push(ctx.spawn(fut::wrap_future(send)));
+                }
+
+                ctx.run_later(step.timeout, move |act, ctx| {
+                    act.abort(format!("timed out waiting for {event:?}"), ctx);
+                })
+            },
+
+            (None, _) => ctx.run_later(step.timeout, |act, ctx| act.advance(ctx)),
+        };
+
+        if let Some(ref mut run) = self.run {
+            run.tasks.push(handle);
+        }
+    }
+
+    fn finish_step(&mut self, ctx: &mut Context<Self>) -> Option<&mut Run> {
+        let run = self.run.as_mut()?;
+
+        for handle in run.tasks.drain(..) {
+            ctx.cancel_future(handle);
+        }
+
+        run.waiting_for = None;
+        Some(run)
+    }
+
+    /// The current step succeeded: move on to the next one.
+    fn advance(&mut self, ctx: &mut Context<Self>) {
+        let Some(run) = self.finish_step(ctx) else {
+            return;
+        };
+
+        let report = Report::StepDone {
+            name:  run.name.clone(),
+            index: run.index,
+            step:  run.steps[run.index],
+        };
+
+        run.index += 1;
+
+        self.report(report);
+        self.run_step(ctx);
+    }
+
+    fn abort(&mut self, reason: String, ctx: &mut Context<Self>) {
+        let Some(run) = self.finish_step(ctx) else {
+            return;
+        };
+
+        tracing::error!(name = %run.name, index = run.index, %reason, "sequence aborted");
+
+        let report = Report::Aborted {
+            name: run.name.clone(),
+            index: run.index,
+            reason,
+        };
+
+        self.run = None;
+        self.report(report);
+    }
+}
+
+impl Actor for Sequencer {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, ground::UpCommand>(ctx);
+            self.subscribe_async::<SystemBroker, serial::DownMessage>(ctx);
+        });
+
+        // the running step's futures belonged to the previous context
+        if let Some(run) = self.run.take() {
+            tracing::warn!(name = %run.name, "sequence interrupted by restart");
+
+            self.report(Report::Aborted {
+                name:   run.name,
+                index:  run.index,
+                reason: "sequencer restarted".to_owned(),
+            });
+        }
+
+        tracing::info!("sequencer started");
+    }
+}
+
+impl Supervised for Sequencer {
+    fn restarting(&mut self, _ctx: &mut <Self as Actor>::Context) {
+        tracing::warn!("sequencer restarting");
+    }
+}
+
+impl Handler<ground::UpCommand> for Sequencer {
+    type Result = ();
+
+    #[tracing::instrument(skip_all)]
+    fn handle(&mut self, ground::UpCommand(msg): ground::UpCommand, ctx: &mut Self::Context) {
+        if msg.header.header.ty.event != Event::FESequence {
+            return;
+        }
+
+        let cmd = match Command::unpack_from_slice(msg.payload.as_ref()) {
+            Ok(cmd) => cmd,
+            Err(e) => return self.reject(format!("bad command payload: {e}")),
+        };
+
+        match cmd {
+            Command::Define {
+                name,
+                steps,
+            } => self.define(name, steps),
+            Command::Delete {
+                name,
+            } => self.delete(name),
+            Command::Run {
+                name,
+            } => self.start(name, ctx),
+            Command::Abort => {
+                if self.run.is_none() {
+                    return self.reject("no sequence running");
+                }
+
+                self.abort("aborted from the ground".to_owned(), ctx);
+            },
+            Command::List => {
+                let stored = self
+                    .sequences
+                    .iter()
+                    .map(|(name, steps)| Summary {
+                        name:  name.clone(),
+                        steps: steps.len(),
+                    })
+                    .collect();
+
+                self.report(Report::Stored(stored));
+            },
+        }
+    }
+}
+
+impl Handler<serial::DownMessage> for Sequencer {
+    type Result = ();
+
+    fn handle(&mut self, msg: serial::DownMessage, ctx: &mut Self::Context) -> Self::Result {
+        let ty = msg.0.header.header.ty;
+
+        let waiting = self.run.as_ref().and_then(|run| run.waiting_for);
+
+        if ty.disposition == Disposition::Command && waiting == Some(ty.event) {
+            tracing::info!(event = ?ty.event, "sequence step event arrived");
+            self.advance(ctx);
+        }
+    }
+}
@@ -0,0 +1,27 @@
+//! `#[serde(with = "crate::serde_millis")]` helper: represents a `Duration` as an integer count
+//! of milliseconds, which is much friendlier to write by hand in config files than serde's default
+//! `{ secs, nanos }` representation.
+
+use std::time::Duration;
+
+use serde::{
+    Deserialize,
+    Deserializer,
+    Serializer,
+};
+
+#[inline]
+pub fn serialize<S>(dur: &Duration, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u64(dur.as_millis() as u64)
+}
+
+#[inline]
+pub fn deserialize<'de, D>(deserializer: D) -> Result<Duration, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    u64::deserialize(deserializer).map(Duration::from_millis)
+}
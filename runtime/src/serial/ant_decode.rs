@@ -13,13 +13,20 @@ use actix_broker::{
 };
 use packed_struct::PackedStructSlice;
 
-use crate::serial::{
-    AntMessage,
-    DownMessage,
+use crate::{
+    ground,
+    serial,
+    serial::{
+        AntMessage,
+        DownMessage,
+    },
 };
-use message::header::{
-    Disposition,
-    Event,
+use message::{
+    downlink::telemetry::Telemetry,
+    header::{
+        Disposition,
+        Event,
+    },
 };
 
 pub struct AntDecode {
@@ -51,6 +58,7 @@ impl Handler<DownMessage> for AntDecode {
 
     #[tracing::instrument(skip_all, fields(%msg))]
     fn handle(&mut self, DownMessage(msg): DownMessage, _ctx: &mut Self::Context) -> Self::Result {
+        let received = message::MissionEpoch::now();
         let msg = msg.as_ref();
 
         if !matches!(msg.header.header.ty, message::header::MessageType {
@@ -75,7 +83,16 @@ impl Handler<DownMessage> for AntDecode {
         let ant_hdr = &relay.payload.header;
         let ant = &relay.payload.payload;
 
-        tracing::info!(cs = %cs.display(), %ant_hdr, ant = %ant.display(), limit_downlink = true, "decoded relay message");
-        self.issue_system_async(AntMessage(relay.payload));
+        // the relay is downlinked as telemetry below; this is for the local log only
+        tracing::debug!(cs = %cs.display(), %ant_hdr, ant = %ant.display(), "decoded relay message");
+        self.issue_system_async(serial::Telemetry {
+            cs:  relay.header,
+            ant: relay.payload.payload,
+        });
+        self.issue_system_async(AntMessage(relay.payload.clone()));
+        self.issue_system_async(ground::TelemetryReport(Telemetry {
+            received,
+            relay,
+        }));
     }
 }
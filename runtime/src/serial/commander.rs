@@ -1,6 +1,7 @@
 use std::time::Duration;
 
 use actix::{
+    fut,
     Actor,
     AsyncContext,
     Context,
@@ -17,16 +18,25 @@ use actix_broker::{
     SystemBroker,
 };
 use futures::future::BoxFuture;
-use tokio::sync::oneshot;
+use tokio::{
+    sync::oneshot,
+    time::Instant,
+};
 
 use message::{
+    downlink::{
+        link::Report,
+        queue::Priority,
+    },
     SourceInfo,
     UniqueId,
 };
 
 use crate::{
+    ground,
     serial,
     serial::AntMessage,
+    system,
     OverrideRegistry,
 };
 
@@ -38,19 +48,50 @@ pub enum Response {
 }
 
 #[derive(Debug, Clone, PartialEq, Message)]
-#[rtype(result = "Option<Response>")]
-pub struct Request(pub message::Message);
+#[rtype(result = "Result<Response, Error>")]
+pub struct Request {
+    pub message: message::Message,
+    /// How long the requester will wait for the ack. The request is forgotten after this, or after
+    /// [`UNACKED_EXPIRY`] if unset.
+    pub timeout: Option<Duration>,
+}
+
+/// How long to remember a request whose requester isn't waiting for a reply with a timeout.
+pub const UNACKED_EXPIRY: Duration = Duration::from_secs(30);
+
+/// How often to forget requests that are no longer awaiting their ack.
+const GARBAGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A command awaiting its ack.
+struct Pending {
+    /// CRC of the command as sent, which the ack must echo.
+    checksum: Option<u8>,
+    /// When the requester stops waiting for the ack.
+    deadline: Instant,
+    tx:       oneshot::Sender<Result<Response, Error>>,
+}
+
+impl Pending {
+    /// Whether anyone is still waiting for this request's ack.
+    #[inline]
+    fn live(&self, now: Instant) -> bool {
+        !self.tx.is_closed() && now < self.deadline
+    }
+}
 
 pub struct Commander {
-    requests: fnv::FnvHashMap<UniqueId, oneshot::Sender<Response>>,
-    once:     std::sync::Once,
+    requests:   fnv::FnvHashMap<UniqueId, Pending>,
+    once:       std::sync::Once,
+    /// Acks rejected because they echoed the wrong command checksum.
+    mismatches: u32,
 }
 
 impl Default for Commander {
     fn default() -> Self {
         Self {
-            requests: Default::default(),
-            once:     std::sync::Once::new(),
+            requests:   Default::default(),
+            once:       std::sync::Once::new(),
+            mismatches: 0,
         }
     }
 }
@@ -63,11 +104,23 @@ pub enum Error {
     #[error("request was dropped")]
     RequestDropped,
 
-    #[error("original command checksum was invalid")]
-    InvalidChecksum,
+    #[error("no ack before the request timed out")]
+    Expired,
+
+    #[error("ack echoed command checksum {actual:#04x}, expected {expected:#04x}")]
+    InvalidChecksum {
+        expected: u8,
+        actual:   u8,
+    },
 
     #[error("response indicated command checksum was invalid")]
     InvalidAckChecksum,
+
+    #[error("dropped from the {0} serial queue")]
+    Dropped(Priority),
+
+    #[error("no free sequence number to re-stamp request colliding with in-flight {0}")]
+    IdCollision(UniqueId),
 }
 
 #[tracing::instrument(skip(message, retry_strategy), err(Display))]
@@ -96,7 +149,10 @@ pub async fn send_retry(
 #[inline]
 #[tracing::instrument(fields(%message), err(Display))]
 pub async fn send(message: message::Message, timeout: Option<Duration>) -> Result<Response, Error> {
-    let req = OverrideRegistry::query::<Request, Commander>().await.send(Request(message));
+    let req = OverrideRegistry::query::<Request, Commander>().await.send(Request {
+        message,
+        timeout,
+    });
 
     tracing::debug!("serial message queued, awaiting reply");
     let resp = match timeout {
@@ -104,7 +160,7 @@ pub async fn send(message: message::Message, timeout: Option<Duration>) -> Resul
         None => req.await,
     };
 
-    let resp = resp?.ok_or(Error::RequestDropped)?;
+    let resp = resp??;
     tracing::debug!("reply received");
 
     let header = match &resp {
@@ -121,22 +177,148 @@ pub async fn send(message: message::Message, timeout: Option<Duration>) -> Resul
 
 #[inline]
 pub async fn do_send(message: message::Message) {
-    OverrideRegistry::query::<Request, Commander>().await.do_send(Request(message))
+    OverrideRegistry::query::<Request, Commander>().await.do_send(Request {
+        message,
+        timeout: None,
+    })
 }
 
 impl Commander {
-    fn collect_garbage(&mut self) {
+    /// Forget requests nobody is waiting on any more, freeing their sequence numbers. The
+    /// requester's end of the channel stays open until we resolve it (actix holds on to the
+    /// response future), so requests that are never acked are only noticed by their deadline.
+    fn collect_garbage(&mut self, ctx: &mut Context<Self>) {
+        let now = Instant::now();
+
         let keys = self
             .requests
             .iter()
-            .filter(|(_, v)| v.is_closed())
+            .filter(|(_, v)| !v.live(now))
             .map(|(id, _)| *id)
             .collect::<Vec<_>>();
 
         keys.into_iter().for_each(|k| {
-            self.requests.remove(&k);
+            if let Some(pending) = self.requests.remove(&k) {
+                let _ = pending.tx.send(Err(Error::Expired));
+            }
+
+            Self::release(k.seq(), ctx);
         });
     }
+
+    /// Hold `seq` for a request we've sent. The commander waits on the seq service so that holds
+    /// and releases arrive in the order they're made.
+    #[inline]
+    fn hold(seq: u8, ctx: &mut Context<Self>) {
+        ctx.wait(fut::wrap_future(system::hold_seq(seq)));
+    }
+
+    #[inline]
+    fn release(seq: u8, ctx: &mut Context<Self>) {
+        ctx.wait(fut::wrap_future(system::release_seq(seq)));
+    }
+
+    /// Whether a request with `id` is still awaiting its ack.
+    #[inline]
+    fn in_flight(&self, id: &UniqueId) -> bool {
+        self.requests.get(id).map_or(false, |pending| pending.live(Instant::now()))
+    }
+
+    /// Make sure `msg` doesn't share an id with an in-flight request, which would steal its ack.
+    /// Our own requests are stamped with sequence numbers that aren't in flight, so in practice
+    /// this catches commands forwarded from the ground: these are re-stamped with a free sequence
+    /// number.
+    fn deconflict(&mut self, msg: message::Message) -> Result<message::Message, Error> {
+        let header = msg.header.header;
+        let id = header.unique_id();
+
+        if !self.in_flight(&id) {
+            return Ok(msg);
+        }
+
+        let seq = (1..=u8::MAX)
+            .map(|n| header.seq.wrapping_add(n))
+            .find(|&seq| {
+                !self.in_flight(
+                    &message::Header {
+                        seq,
+                        ..header
+                    }
+                    .unique_id(),
+                )
+            })
+            .ok_or(Error::IdCollision(id))?;
+
+        let mut inner = msg.take();
+        inner.header.header.seq = seq;
+
+        let msg = message::Message::new(inner);
+        let restamped = msg.header.header.unique_id();
+
+        tracing::warn!(original = %id, %restamped, "request id collides with in-flight request, re-stamped");
+
+        self.issue_system_async(ground::LinkReport(Report::IdCollision {
+            event: header.ty.event,
+            original: id,
+            restamped,
+        }));
+
+        Ok(msg)
+    }
+
+    /// Resolve the request acked by `info` with `response`, provided the ack echoes the checksum of
+    /// the command we sent. An ack that doesn't is rejected: the request stays pending (holding its
+    /// sequence number) until the matching ack arrives or its deadline passes.
+    fn resolve(
+        &mut self,
+        info: message::source_info::Info,
+        response: Response,
+        ctx: &mut Context<Self>,
+    ) {
+        let msg_id = info.header.unique_id();
+
+        let Some(pending) = self.requests.get(&msg_id) else {
+            tracing::warn!(?msg_id, "received ack message for unregistered command");
+            return;
+        };
+
+        if let Some(expected) = pending.checksum.filter(|&expected| expected != info.checksum) {
+            self.mismatches += 1;
+
+            let error = Error::InvalidChecksum {
+                expected,
+                actual: info.checksum,
+            };
+
+            tracing::error!(
+                ?msg_id,
+                %error,
+                mismatches = self.mismatches,
+                "rejecting ack"
+            );
+
+            self.issue_system_async(ground::LinkReport(Report::AckChecksumMismatch {
+                event: info.header.ty.event,
+                expected,
+                actual: info.checksum,
+                mismatches: self.mismatches,
+            }));
+
+            return;
+        }
+
+        let Some(pending) = self.requests.remove(&msg_id) else {
+            return;
+        };
+
+        Self::release(msg_id.seq(), ctx);
+
+        tracing::debug!("found listener for message, replying");
+
+        if let Err(_msg) = pending.tx.send(Ok(response)) {
+            tracing::warn!(?msg_id, "tried to ack command, listener dropped");
+        }
+    }
 }
 
 impl Actor for Commander {
@@ -147,10 +329,11 @@ impl Actor for Commander {
         self.once.call_once(|| {
             self.subscribe_async::<SystemBroker, serial::AckMessage>(ctx);
             self.subscribe_async::<SystemBroker, AntMessage>(ctx);
+            self.subscribe_async::<SystemBroker, serial::queue::Dropped>(ctx);
         });
 
-        ctx.run_interval(Duration::from_secs(5), |a, _ctx| {
-            a.collect_garbage();
+        ctx.run_interval(GARBAGE_INTERVAL, |a, ctx| {
+            a.collect_garbage(ctx);
         });
     }
 }
@@ -159,21 +342,45 @@ impl Supervised for Commander {}
 impl SystemService for Commander {}
 
 impl Handler<Request> for Commander {
-    type Result = ResponseFuture<Option<Response>>;
+    type Result = ResponseFuture<Result<Response, Error>>;
+
+    fn handle(&mut self, req: Request, ctx: &mut Self::Context) -> Self::Result {
+        let msg = match self.deconflict(req.message) {
+            Ok(msg) => msg,
+            Err(e) => {
+                tracing::error!(error = %e, "rejecting request");
+                return Box::pin(async move { Err(e) });
+            },
+        };
 
-    fn handle(&mut self, msg: Request, _ctx: &mut Self::Context) -> Self::Result {
         let (tx, rx) = oneshot::channel();
-        self.requests.insert(msg.0.as_ref().header.header.unique_id(), tx);
-        self.issue_async::<SystemBroker, _>(serial::UpMessage(msg.0));
+        let checksum = msg.checksum().ok().and_then(|c| c.first().copied());
+        let id = msg.header.header.unique_id();
 
-        Box::pin(async move { rx.await.ok() })
+        // anything already under this id is no longer awaiting its ack
+        if self
+            .requests
+            .insert(id, Pending {
+                checksum,
+                deadline: Instant::now() + req.timeout.unwrap_or(UNACKED_EXPIRY),
+                tx,
+            })
+            .is_some()
+        {
+            Self::release(id.seq(), ctx);
+        }
+
+        Self::hold(id.seq(), ctx);
+        self.issue_async::<SystemBroker, _>(serial::UpMessage(msg));
+
+        Box::pin(async move { rx.await.unwrap_or(Err(Error::RequestDropped)) })
     }
 }
 
 impl Handler<serial::AckMessage> for Commander {
     type Result = ();
 
-    fn handle(&mut self, msg: serial::AckMessage, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: serial::AckMessage, ctx: &mut Self::Context) -> Self::Result {
         // drop relay packets in favor of the inner ant message
         if matches!(msg.0.header.header.ty, message::header::MessageType {
             event: message::header::Event::CSRelay,
@@ -187,37 +394,38 @@ impl Handler<serial::AckMessage> for Commander {
             return;
         };
 
-        let msg_id = info.header.unique_id();
-
-        let Some(tx) = self.requests.remove(&msg_id) else {
-            tracing::warn!(?msg_id, "received ack message for unregistered command");
-            return
-        };
-
-        tracing::debug!("found listener for message, replying");
-
-        if let Err(_msg) = tx.send(Response::Message(msg.0)) {
-            tracing::warn!(?msg_id, "tried to ack command, listener dropped");
-        }
+        self.resolve(info, Response::Message(msg.0), ctx);
     }
 }
 
 impl Handler<AntMessage> for Commander {
     type Result = ();
 
-    fn handle(&mut self, msg: AntMessage, _ctx: &mut Self::Context) -> Self::Result {
+    fn handle(&mut self, msg: AntMessage, ctx: &mut Self::Context) -> Self::Result {
         let SourceInfo::Info(info) = msg.0.header.payload else {
             return;
         };
 
-        let msg_id = info.header.unique_id();
+        // ant packets that don't answer one of our commands are routine
+        if !self.requests.contains_key(&info.header.unique_id()) {
+            return;
+        }
+
+        self.resolve(info, Response::Ant(msg.0), ctx);
+    }
+}
 
-        let Some(tx) = self.requests.remove(&info.header.unique_id()) else {
+impl Handler<serial::queue::Dropped> for Commander {
+    type Result = ();
+
+    fn handle(&mut self, msg: serial::queue::Dropped, ctx: &mut Self::Context) -> Self::Result {
+        let Some(pending) = self.requests.remove(&msg.id) else {
             return;
         };
 
-        if let Err(_e) = tx.send(Response::Ant(msg.0)) {
-            tracing::warn!(?msg_id, "got ant ack for valid command whose listener was dropped");
-        }
+        Self::release(msg.id.seq(), ctx);
+
+        // the requester may not be waiting for a reply
+        let _ = pending.tx.send(Err(Error::Dropped(msg.priority)));
     }
 }
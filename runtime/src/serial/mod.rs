@@ -22,7 +22,9 @@ use message::{
 
 pub mod ant_decode;
 mod commander;
+pub mod queue;
 pub mod raw;
+pub mod watchdog;
 
 pub use commander::*;
 
@@ -42,6 +44,14 @@ pub struct AckMessage(pub Message);
 #[rtype(result = "()")]
 pub struct AntMessage(pub message::AntPacket);
 
+/// Telemetry carried by a relayed ant message: the central station's own status and the ant's.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, actix::Message)]
+#[rtype(result = "()")]
+pub struct Telemetry {
+    pub cs:  message::cs::Payload,
+    pub ant: message::ant::Payload,
+}
+
 #[tracing::instrument(skip_all, fields(%msg))]
 fn try_issue_ack<A>(issue: &A, msg: &Message)
 where
@@ -105,7 +115,11 @@ impl Handler<UpMessage> for Serial {
 
         tracing::info!(packed = %hex::encode(&packed), limit_downlink = true, "send serial packet");
 
-        self.issue_async::<SystemBroker, _>(raw::UpPacket(packed));
+        self.issue_async::<SystemBroker, _>(queue::Enqueue {
+            id:     msg.0.header.header.unique_id(),
+            event:  msg.0.header.header.ty.event,
+            packet: packed,
+        });
     }
 }
 
@@ -0,0 +1,489 @@
+use std::{
+    collections::VecDeque,
+    sync::Once,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+use bytes::Bytes;
+
+use message::{
+    downlink::queue::{
+        ClassStats,
+        Priority,
+        Report,
+    },
+    header::Event,
+    UniqueId,
+};
+
+use crate::{
+    config,
+    ground,
+    serial::raw,
+};
+
+/// A packed message to be scheduled onto the serial port.
+#[derive(Clone, Debug, PartialEq, Eq, Message)]
+#[rtype(result = "()")]
+pub struct Enqueue {
+    pub id:     UniqueId,
+    pub event:  Event,
+    pub packet: Bytes,
+}
+
+/// A queued message was dropped or rejected and will never be sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Message)]
+#[rtype(result = "()")]
+pub struct Dropped {
+    pub id:       UniqueId,
+    pub priority: Priority,
+}
+
+/// The scheduling class of a command.
+pub fn classify(event: Event) -> Priority {
+    match event {
+        Event::AntStop | Event::AntPowerOff | Event::AntHeaterOff | Event::CSRoverStop => {
+            Priority::Safety
+        },
+        Event::CSAntDFUInit | Event::CSAntDFUPacket => Priority::Bulk,
+        _ => Priority::Control,
+    }
+}
+
+/// What to do with a message arriving at a full queue.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Overflow {
+    /// Turn the new message away.
+    Reject,
+    /// Drop the oldest queued message to make room.
+    DropOldest,
+}
+
+/// Limits for one priority class.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Class {
+    /// Most messages queued at once.
+    pub capacity: u32,
+    pub overflow: Overflow,
+}
+
+/// Serial uplink scheduling parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Share of the serial line rate (at 10 bits per byte) we allow ourselves to use.
+    pub budget_percent: u8,
+
+    /// Unused budget accumulates up to this much time's worth, to be spent in a burst.
+    #[serde(with = "crate::serde_millis")]
+    pub burst: Duration,
+
+    pub safety:  Class,
+    pub control: Class,
+    pub bulk:    Class,
+
+    #[serde(with = "crate::serde_millis")]
+    pub report_interval: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            budget_percent:  80,
+            burst:           Duration::from_secs(1),
+            safety:          Class {
+                capacity: 16,
+                overflow: Overflow::DropOldest,
+            },
+            control:         Class {
+                capacity: 64,
+                overflow: Overflow::Reject,
+            },
+            bulk:            Class {
+                capacity: 256,
+                overflow: Overflow::Reject,
+            },
+            report_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if !(1..=100).contains(&self.budget_percent) {
+            return Err(config::Error {
+                name:   "serial_queue.budget_percent",
+                reason: "must be between 1 and 100",
+            });
+        }
+
+        if self.burst.is_zero() {
+            return Err(config::Error {
+                name:   "serial_queue.burst",
+                reason: "must be nonzero",
+            });
+        }
+
+        for (name, class) in [
+            ("serial_queue.safety.capacity", self.safety),
+            ("serial_queue.control.capacity", self.control),
+            ("serial_queue.bulk.capacity", self.bulk),
+        ] {
+            if class.capacity == 0 {
+                return Err(config::Error {
+                    name,
+                    reason: "must be nonzero",
+                });
+            }
+        }
+
+        if self.report_interval.is_zero() {
+            return Err(config::Error {
+                name:   "serial_queue.report_interval",
+                reason: "must be nonzero",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            budget_percent = self.budget_percent,
+            burst = ?self.burst,
+            safety = ?self.safety,
+            control = ?self.control,
+            bulk = ?self.bulk,
+            report_interval = ?self.report_interval,
+            "serial queue config"
+        );
+    }
+
+    #[inline]
+    pub fn class(&self, priority: Priority) -> Class {
+        match priority {
+            Priority::Safety => self.safety,
+            Priority::Control => self.control,
+            Priority::Bulk => self.bulk,
+        }
+    }
+
+    /// Bytes per second we may write at `baud`.
+    #[inline]
+    pub fn budget(&self, baud: u32) -> u32 {
+        // 8N1: 10 bits on the wire per byte
+        ((baud as u64 / 10) * self.budget_percent as u64 / 100).max(1) as u32
+    }
+}
+
+/// Token bucket pacing writes to a byte rate.
+#[derive(Debug, Clone)]
+pub struct Bucket {
+    /// Bytes per second.
+    rate:     f64,
+    capacity: f64,
+    tokens:   f64,
+    last:     Instant,
+}
+
+impl Bucket {
+    /// A full bucket refilling at `rate` bytes per second, holding up to `burst` worth.
+    pub fn new(rate: u32, burst: Duration, now: Instant) -> Self {
+        let rate = rate as f64;
+        let capacity = rate * burst.as_secs_f64();
+
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+            last: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last).as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.capacity);
+        self.last = now;
+    }
+
+    /// How long until `len` bytes can be written. A message larger than the bucket goes out once
+    /// the bucket is full, leaving it in debt.
+    pub fn delay(&mut self, len: usize, now: Instant) -> Duration {
+        self.refill(now);
+
+        let needed = (len as f64).min(self.capacity) - self.tokens;
+
+        if needed <= 0. {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(needed / self.rate)
+        }
+    }
+
+    /// Spend `len` bytes' worth. Check [`Bucket::delay`] first.
+    #[inline]
+    pub fn take(&mut self, len: usize) {
+        self.tokens -= len as f64;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Queued {
+    pub id:     UniqueId,
+    pub event:  Event,
+    pub packet: Bytes,
+    pub queued: Instant,
+}
+
+/// Outcome of queueing a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Push {
+    Queued,
+    /// Queued, but the oldest message in its class was dropped to make room.
+    Dropped(Queued),
+    /// The class was full: the message was not queued.
+    Rejected(Queued),
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Stats {
+    sent:          u32,
+    dropped:       u32,
+    rejected:      u32,
+    total_latency: Duration,
+    max_latency:   Duration,
+}
+
+/// Bounded per-class queues, served strictly in priority order.
+#[derive(Debug, Clone)]
+pub struct Queues {
+    config: Config,
+    queues: [VecDeque<Queued>; 3],
+    stats:  [Stats; 3],
+}
+
+impl Queues {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            queues: Default::default(),
+            stats: Default::default(),
+        }
+    }
+
+    #[inline]
+    fn index(priority: Priority) -> usize {
+        match priority {
+            Priority::Safety => 0,
+            Priority::Control => 1,
+            Priority::Bulk => 2,
+        }
+    }
+
+    pub fn push(&mut self, priority: Priority, item: Queued) -> Push {
+        let i = Self::index(priority);
+        let class = self.config.class(priority);
+        let queue = &mut self.queues[i];
+
+        if queue.len() < class.capacity as usize {
+            queue.push_back(item);
+            return Push::Queued;
+        }
+
+        match class.overflow {
+            Overflow::Reject => {
+                self.stats[i].rejected += 1;
+                Push::Rejected(item)
+            },
+            Overflow::DropOldest => {
+                let oldest = queue.pop_front().expect("capacity is nonzero");
+                queue.push_back(item);
+
+                self.stats[i].dropped += 1;
+                Push::Dropped(oldest)
+            },
+        }
+    }
+
+    /// The next message to send.
+    #[inline]
+    pub fn peek(&self) -> Option<&Queued> {
+        self.queues.iter().find_map(|q| q.front())
+    }
+
+    /// Take the next message to send, recording how long it waited.
+    pub fn pop(&mut self, now: Instant) -> Option<Queued> {
+        let i = self.queues.iter().position(|q| !q.is_empty())?;
+        let item = self.queues[i].pop_front()?;
+
+        let latency = now.saturating_duration_since(item.queued);
+        let stats = &mut self.stats[i];
+
+        stats.sent += 1;
+        stats.total_latency += latency;
+        stats.max_latency = stats.max_latency.max(latency);
+
+        Some(item)
+    }
+
+    #[inline]
+    pub fn depth(&self, priority: Priority) -> usize {
+        self.queues[Self::index(priority)].len()
+    }
+
+    /// Per-class statistics since the last call.
+    pub fn take_stats(&mut self) -> Vec<ClassStats> {
+        Priority::ALL
+            .iter()
+            .map(|&priority| {
+                let i = Self::index(priority);
+                let stats = std::mem::take(&mut self.stats[i]);
+
+                ClassStats {
+                    priority,
+                    depth: self.queues[i].len() as u32,
+                    sent: stats.sent,
+                    dropped: stats.dropped,
+                    rejected: stats.rejected,
+                    mean_latency: stats.total_latency.checked_div(stats.sent).unwrap_or_default(),
+                    max_latency: stats.max_latency,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Sits between the serial message layer and [`raw::RawIO`]: queues outgoing messages by priority
+/// class and releases them to the serial port within a bandwidth budget, so that a burst of bulk
+/// traffic can't hold up a safety command.
+pub struct SerialQueue {
+    config:         Config,
+    subscribe_once: Once,
+    /// Bytes per second.
+    budget:         u32,
+    bucket:         Bucket,
+    queues:         Queues,
+    /// Wakeup for when the bucket will allow the next message.
+    timer:          Option<SpawnHandle>,
+}
+
+impl SerialQueue {
+    pub fn new(config: Config, baud: u32) -> Self {
+        let budget = config.budget(baud);
+
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            budget,
+            bucket: Bucket::new(budget, config.burst, Instant::now()),
+            queues: Queues::new(config),
+            timer: None,
+        }
+    }
+
+    fn drain(&mut self, ctx: &mut Context<Self>) {
+        if self.timer.is_some() {
+            return;
+        }
+
+        while let Some(next) = self.queues.peek() {
+            let now = Instant::now();
+            let delay = self.bucket.delay(next.packet.len(), now);
+
+            if !delay.is_zero() {
+                self.timer = Some(ctx.run_later(delay, |a, ctx| {
+                    a.timer = None;
+                    a.drain(ctx);
+                }));
+
+                return;
+            }
+
+            let Some(item) = self.queues.pop(now) else {
+                return;
+            };
+
+            tracing::debug!(id = %item.id, event = ?item.event, len = item.packet.len(), "writing queued serial packet");
+
+            self.bucket.take(item.packet.len());
+            self.issue_system_async(raw::UpPacket(item.packet));
+        }
+    }
+
+    fn report(&mut self) {
+        self.issue_system_async(ground::QueueReport(Report::Stats {
+            budget:  self.budget,
+            classes: self.queues.take_stats(),
+        }));
+    }
+}
+
+impl Actor for SerialQueue {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, Enqueue>(ctx);
+        });
+
+        // any timer belonged to the previous context
+        self.timer = None;
+
+        ctx.run_interval(self.config.report_interval, |a, _ctx| a.report());
+        self.drain(ctx);
+
+        tracing::info!(budget = self.budget, "serial queue started");
+    }
+}
+
+impl Supervised for SerialQueue {}
+
+impl Handler<Enqueue> for SerialQueue {
+    type Result = ();
+
+    #[tracing::instrument(skip_all, fields(id = %msg.id, event = ?msg.event))]
+    fn handle(&mut self, msg: Enqueue, ctx: &mut Self::Context) -> Self::Result {
+        let priority = classify(msg.event);
+
+        let item = Queued {
+            id:     msg.id,
+            event:  msg.event,
+            packet: msg.packet,
+            queued: Instant::now(),
+        };
+
+        let lost = match self.queues.push(priority, item) {
+            Push::Queued => None,
+            Push::Dropped(oldest) => {
+                tracing::warn!(%priority, dropped = %oldest.id, event = ?oldest.event, "serial queue full, dropped oldest");
+                Some(oldest)
+            },
+            Push::Rejected(item) => {
+                tracing::warn!(%priority, "serial queue full, rejected");
+                Some(item)
+            },
+        };
+
+        if let Some(lost) = lost {
+            self.issue_system_async(Dropped {
+                id: lost.id,
+                priority,
+            });
+        }
+
+        self.drain(ctx);
+    }
+}
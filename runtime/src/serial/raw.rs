@@ -1,6 +1,13 @@
 use std::{
+    path::{
+        Path,
+        PathBuf,
+    },
     sync::Once,
-    time::Duration,
+    time::{
+        Duration,
+        Instant,
+    },
 };
 
 use actix::{
@@ -44,6 +51,20 @@ use antrelay_codec::{
     },
     CobsCodec,
 };
+use message::{
+    downlink::link::Report,
+    header::Event,
+    payload::{
+        log::Type,
+        realtime_status::Flags,
+    },
+};
+
+use crate::{
+    config,
+    ground,
+    housekeeping,
+};
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Message, derive_more::Into, derive_more::AsRef)]
 #[rtype(result = "()")]
@@ -53,20 +74,250 @@ pub struct UpPacket(pub Bytes);
 #[rtype(result = "()")]
 pub struct DownPacket(pub Bytes);
 
+/// Enter (`Some`) or leave (`None`) maintenance mode. In maintenance mode, `RawIO` stops relaying:
+/// [`UpPacket`]s are dropped, and every frame received from the serial port is sent to the provided
+/// channel instead of being issued as a [`DownPacket`]. Only [`MaintenancePacket`]s are written to
+/// the serial port.
+///
+/// Maintenance mode ends on its own if the channel is closed or the serial connection restarts.
+#[derive(Clone, Debug, Message)]
+#[rtype(result = "()")]
+pub struct Maintenance(pub Option<mpsc::UnboundedSender<Bytes>>);
+
+/// Drop the serial connection and reconnect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Message)]
+#[rtype(result = "()")]
+pub struct Reconnect;
+
+/// A frame to write to the serial port in maintenance mode.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Message, derive_more::Into, derive_more::AsRef)]
+#[rtype(result = "()")]
+pub struct MaintenancePacket(pub Bytes);
+
+/// Serial reconnection parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Delay before the first reconnection attempt. Doubles with each consecutive failure.
+    #[serde(with = "crate::serde_millis")]
+    pub base: Duration,
+
+    /// Longest delay between reconnection attempts.
+    #[serde(with = "crate::serde_millis")]
+    pub max_delay: Duration,
+
+    /// How often to check for the serial device while it's missing.
+    #[serde(with = "crate::serde_millis")]
+    pub poll_interval: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            base:          Duration::from_millis(500),
+            max_delay:     Duration::from_secs(30),
+            poll_interval: Duration::from_millis(250),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        let err = |name, reason| {
+            Err(config::Error {
+                name,
+                reason,
+            })
+        };
+
+        if self.base.is_zero() {
+            return err("serial.base", "must be nonzero");
+        }
+
+        if self.base > self.max_delay {
+            return err("serial.base", "must not exceed max_delay");
+        }
+
+        if self.poll_interval.is_zero() {
+            return err("serial.poll_interval", "must be nonzero");
+        }
+
+        Ok(())
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            base = ?self.base,
+            max_delay = ?self.max_delay,
+            poll_interval = ?self.poll_interval,
+            "serial reconnect config"
+        );
+    }
+
+    /// Jittered delay before reconnecting after `failures` consecutive failures, capped at
+    /// `max_delay`.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        // as for [`crate::table::Retry::strategy`]: multiply by a random sample in the range
+        // [1 - JITTER_FACTOR, 1 + JITTER_FACTOR]
+        const JITTER_FACTOR: f64 = 0.5;
+
+        let factor = 1u32.checked_shl(failures.saturating_sub(1)).unwrap_or(u32::MAX);
+        let delay = self.base.saturating_mul(factor).min(self.max_delay);
+
+        let jitter = (rand::random::<f64>() - 0.5) * JITTER_FACTOR * 2. + 1.;
+
+        delay.mul_f64(jitter).min(self.max_delay)
+    }
+}
+
+/// Identifies a device node, so that a replugged adapter can be told apart from the one we lost.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Identity {
+    dev: u64,
+    ino: u64,
+}
+
+impl Identity {
+    /// The identity of the node at `path`, if there is one.
+    #[cfg(unix)]
+    pub fn of(path: &Path) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+
+        let meta = std::fs::metadata(path).ok()?;
+
+        Some(Self {
+            dev: meta.dev(),
+            ino: meta.ino(),
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn of(path: &Path) -> Option<Self> {
+        path.exists().then_some(Self {
+            dev: 0,
+            ino: 0,
+        })
+    }
+}
+
+/// Wait `delay` before reconnecting to the serial device at `path`, returning early if the device
+/// reappears. If it's still missing once `delay` is up, keep waiting until it's back: there's no
+/// point trying to open a node that doesn't exist.
+///
+/// Returns whether the device came back (or was replaced) while we were waiting.
+async fn await_device(
+    path: PathBuf,
+    last: Option<Identity>,
+    delay: Duration,
+    poll_interval: Duration,
+) -> bool {
+    let deadline = tokio::time::Instant::now() + delay;
+    let mut missing = false;
+
+    loop {
+        match Identity::of(&path) {
+            Some(id) if missing || last.map_or(false, |last| last != id) => {
+                tracing::info!(path = %path.display(), ?id, "serial device reappeared");
+                return true;
+            },
+            Some(_) if tokio::time::Instant::now() >= deadline => return false,
+            Some(_) => {},
+            None if !missing => {
+                tracing::warn!(path = %path.display(), "serial device missing, waiting for it");
+                missing = true;
+            },
+            None => {},
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
 type IO = (Box<dyn AsyncRead + Unpin>, Box<dyn AsyncWrite + Unpin>);
 
 pub struct RawIO {
+    config:         Config,
+    /// Device node to watch while disconnected, if the serial port is one.
+    device:         Option<PathBuf>,
     make_io:        Box<dyn Fn() -> BoxFuture<'static, Option<IO>>>,
     tx:             Option<mpsc::UnboundedSender<Bytes>>,
+    maintenance:    Option<mpsc::UnboundedSender<Bytes>>,
     subscribe_once: Once,
+    /// Identity of the device node when we last connected.
+    identity:       Option<Identity>,
+    /// Consecutive failed connection attempts.
+    failures:       u32,
+    /// Start of the ongoing outage.
+    down_since:     Option<Instant>,
+    /// Outages since startup.
+    outages:        u32,
+    /// Total length of completed outages.
+    down_for:       Duration,
 }
 
 impl RawIO {
-    pub fn new(make_io: Box<dyn Fn() -> BoxFuture<'static, Option<IO>>>) -> Self {
+    pub fn new(
+        config: Config,
+        device: Option<PathBuf>,
+        make_io: Box<dyn Fn() -> BoxFuture<'static, Option<IO>>>,
+    ) -> Self {
         Self {
+            config,
+            device,
             make_io,
             tx: None,
+            maintenance: None,
             subscribe_once: Once::new(),
+            identity: None,
+            failures: 0,
+            down_since: None,
+            outages: 0,
+            down_for: Duration::ZERO,
+        }
+    }
+
+    #[inline]
+    fn report(&self, report: Report) {
+        self.issue_system_async(ground::LinkReport(report));
+    }
+
+    fn connected(&mut self) {
+        self.failures = 0;
+        self.identity = self.device.as_deref().and_then(Identity::of);
+
+        if let Some(since) = self.down_since.take() {
+            let down_for = since.elapsed();
+            self.down_for += down_for;
+
+            tracing::info!(?down_for, outages = self.outages, "serial link restored");
+
+            self.report(Report::SerialRestored {
+                down_for,
+                outages: self.outages,
+            });
+        }
+    }
+
+    fn disconnected(&mut self) {
+        self.failures = self.failures.saturating_add(1);
+
+        if self.down_since.is_none() {
+            self.down_since = Some(Instant::now());
+            self.outages += 1;
+
+            self.report(Report::SerialLost {
+                outages: self.outages,
+            });
+        }
+    }
+
+    fn status(&self) -> Report {
+        Report::SerialStatus {
+            connected: self.tx.is_some(),
+            outages:   self.outages,
+            down_for:  self.down_for,
+            current:   self.down_since.map(|since| since.elapsed()),
         }
     }
 }
@@ -75,17 +326,28 @@ impl Actor for RawIO {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, UpPacket>(ctx);
+            self.subscribe_async::<SystemBroker, Maintenance>(ctx);
+            self.subscribe_async::<SystemBroker, MaintenancePacket>(ctx);
+            self.subscribe_async::<SystemBroker, Reconnect>(ctx);
+            self.subscribe_async::<SystemBroker, ground::UpCommand>(ctx);
+        });
+
         let fut = fut::wrap_future((self.make_io)()).map(
             |result, a: &mut Self, ctx: &mut Context<Self>| {
                 let (r, w) = match result {
                     Some(io) => io,
                     None => {
+                        housekeeping::raise(Flags::SERIAL_ERROR);
                         ctx.stop();
                         return;
                     },
                 };
 
                 tracing::info!("connected to serial port");
+                ground::downlink::lifecycle(Type::SerialConnected);
+                a.connected();
 
                 let (tx, rx) = mpsc::unbounded_channel();
                 let framed_write = FramedWrite::new(w, CobsCodec);
@@ -99,18 +361,19 @@ impl Actor for RawIO {
                     match result {
                         Err(Error::Io(e)) => {
                             tracing::error!(error = %e, "io error -- reconnecting to serial port");
+                            housekeeping::raise(Flags::SERIAL_ERROR);
                             ctx.stop();
                         }
-                        Err(e) => tracing::error!(error = %e),
+                        Err(e) => {
+                            tracing::error!(error = %e);
+                            housekeeping::raise(Flags::SERIAL_ERROR);
+                        },
                         _ => {
                         },
                     }
                 }));
 
                 a.tx = Some(tx);
-                a.subscribe_once.call_once(|| {
-                    a.subscribe_async::<SystemBroker, UpPacket>(ctx);
-                });
 
                 let framed_downlink = FramedRead::new(r, CobsCodec);
 
@@ -123,15 +386,27 @@ impl Actor for RawIO {
                                 Ok(pkt) => pkt,
                                 Err(Error::Io(e)) => {
                                     tracing::error!(error = %e, "io error -- reconnecting to serial port");
+                                    housekeeping::raise(Flags::SERIAL_ERROR);
                                     ctx.stop();
                                     return;
                                 },
                                 Err(e) => {
                                     tracing::error!(error = %e);
+                                    housekeeping::raise(Flags::SERIAL_ERROR);
                                     return;
                                 },
                             };
 
+                            if let Some(ref listener) = a.maintenance {
+                                if listener.send(pkt.0).is_ok() {
+                                    return;
+                                }
+
+                                tracing::warn!("maintenance listener dropped, resuming relay");
+                                a.maintenance = None;
+                                return;
+                            }
+
                             a.issue_async::<SystemBroker, _>(pkt);
                         })
                         .finish(),
@@ -143,16 +418,11 @@ impl Actor for RawIO {
     }
 }
 
-impl Handler<UpPacket> for RawIO {
-    type Result = ();
-
-    #[tracing::instrument(skip_all, fields(msg = %hex::encode(&*msg.0)))]
-    fn handle(&mut self, msg: UpPacket, _ctx: &mut Self::Context) -> Self::Result {
-        tracing::trace!(limit_downlink = true, "sending serial packet");
-
+impl RawIO {
+    fn write(&self, pkt: Bytes) {
         match self.tx {
             Some(ref tx) => {
-                if let Err(_e) = tx.send(msg.0) {
+                if let Err(_e) = tx.send(pkt) {
                     tracing::error!("trying to send serial uplink packet: remote channel dropped");
                 }
             },
@@ -163,13 +433,108 @@ impl Handler<UpPacket> for RawIO {
     }
 }
 
+impl Handler<UpPacket> for RawIO {
+    type Result = ();
+
+    #[tracing::instrument(skip_all, fields(msg = %hex::encode(&*msg.0)))]
+    fn handle(&mut self, msg: UpPacket, _ctx: &mut Self::Context) -> Self::Result {
+        if self.maintenance.is_some() {
+            tracing::warn!("dropping packet -- serial port in maintenance mode");
+            return;
+        }
+
+        tracing::trace!(limit_downlink = true, "sending serial packet");
+        self.write(msg.0);
+    }
+}
+
+impl Handler<MaintenancePacket> for RawIO {
+    type Result = ();
+
+    #[tracing::instrument(skip_all, fields(msg = %hex::encode(&*msg.0)))]
+    fn handle(&mut self, msg: MaintenancePacket, _ctx: &mut Self::Context) -> Self::Result {
+        if self.maintenance.is_none() {
+            tracing::warn!("dropping maintenance packet -- not in maintenance mode");
+            return;
+        }
+
+        tracing::trace!("sending maintenance packet");
+        self.write(msg.0);
+    }
+}
+
+impl Handler<Reconnect> for RawIO {
+    type Result = ();
+
+    fn handle(&mut self, _msg: Reconnect, ctx: &mut Self::Context) -> Self::Result {
+        if self.maintenance.is_some() {
+            tracing::warn!("ignoring reconnect request in maintenance mode");
+            return;
+        }
+
+        tracing::warn!("reconnect requested -- reconnecting to serial port");
+        ctx.stop();
+    }
+}
+
+impl Handler<ground::UpCommand> for RawIO {
+    type Result = ();
+
+    fn handle(&mut self, ground::UpCommand(msg): ground::UpCommand, _ctx: &mut Self::Context) {
+        if msg.header.header.ty.event != Event::FEStatus {
+            return;
+        }
+
+        self.report(self.status());
+    }
+}
+
+impl Handler<Maintenance> for RawIO {
+    type Result = ();
+
+    fn handle(&mut self, msg: Maintenance, _ctx: &mut Self::Context) -> Self::Result {
+        match msg.0 {
+            Some(_) => tracing::warn!("entering maintenance mode: serial relaying suspended"),
+            None if self.maintenance.is_some() => {
+                tracing::info!("leaving maintenance mode: serial relaying restored")
+            },
+            None => {},
+        }
+
+        self.maintenance = msg.0;
+    }
+}
+
 impl Supervised for RawIO
 where
     Self: Actor,
 {
     fn restarting(&mut self, ctx: &mut <Self as Actor>::Context) {
-        tracing::error!("serial connection restarting");
         self.tx = None;
-        ctx.wait(fut::wrap_future(tokio::time::sleep(Duration::from_millis(1000))));
+        self.disconnected();
+
+        if self.maintenance.take().is_some() {
+            tracing::warn!("serial connection restarted in maintenance mode, resuming relay");
+        }
+
+        let delay = self.config.backoff(self.failures);
+        tracing::error!(failures = self.failures, ?delay, "serial connection restarting");
+
+        let device = match self.device.clone() {
+            Some(device) => device,
+            None => {
+                ctx.wait(fut::wrap_future(tokio::time::sleep(delay)));
+                return;
+            },
+        };
+
+        let wait = await_device(device, self.identity, delay, self.config.poll_interval);
+
+        ctx.wait(fut::wrap_future(wait).map(|reappeared, a: &mut Self, _ctx| {
+            // a fresh device deserves a prompt first attempt if it fails again
+            if reappeared {
+                a.failures = 0;
+            }
+        }));
     }
 }
@@ -0,0 +1,238 @@
+use std::{
+    sync::Once,
+    time::Instant,
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+
+use message::downlink::link::Report;
+
+use crate::{
+    config,
+    ground,
+    serial::raw,
+};
+
+/// Outcome of a tracked ping to the central station.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Message)]
+#[rtype(result = "()")]
+pub struct PingResult {
+    pub acked: bool,
+}
+
+/// Central station liveness parameters.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Consecutive missed pings before the central station is declared unresponsive (and again
+    /// every this many misses while it stays that way).
+    pub missed_pings: u32,
+
+    /// Whether to reconnect the serial port when the central station is unresponsive.
+    pub reconnect: bool,
+
+    /// Consecutive missed pings after which to ask the rover to power-cycle the central station.
+    /// Never asks if unset.
+    pub power_cycle_after: Option<u32>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            missed_pings:      3,
+            reconnect:         true,
+            power_cycle_after: Some(12),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if self.missed_pings == 0 {
+            return Err(config::Error {
+                name:   "watchdog.missed_pings",
+                reason: "must be at least 1",
+            });
+        }
+
+        if self.power_cycle_after.map_or(false, |n| n < self.missed_pings) {
+            return Err(config::Error {
+                name:   "watchdog.power_cycle_after",
+                reason: "must not be less than missed_pings",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// How the watchdog responds to a ping that was `acked` (or not) after `missed` consecutive
+    /// misses.
+    pub fn tally(&self, missed: u32, acked: bool) -> Tally {
+        if acked {
+            return Tally {
+                missed: 0,
+                recovered: missed >= self.missed_pings,
+                ..Default::default()
+            };
+        }
+
+        let missed = missed.saturating_add(1);
+        let alarm = missed % self.missed_pings == 0;
+
+        Tally {
+            missed,
+            unresponsive: missed == self.missed_pings,
+            reconnect: alarm && self.reconnect,
+            power_cycle: self.power_cycle_after == Some(missed),
+            recovered: false,
+        }
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            missed_pings = self.missed_pings,
+            reconnect = self.reconnect,
+            power_cycle_after = ?self.power_cycle_after,
+            "central station watchdog config"
+        );
+    }
+}
+
+/// Outcome of a single ping result. See [`Config::tally`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Tally {
+    /// Consecutive missed pings, including this one.
+    pub missed:       u32,
+    /// The central station has just been declared unresponsive.
+    pub unresponsive: bool,
+    /// Reconnect the serial port.
+    pub reconnect:    bool,
+    /// Ask the rover to power-cycle the central station.
+    pub power_cycle:  bool,
+    /// The central station answered after having been declared unresponsive.
+    pub recovered:    bool,
+}
+
+/// Tracks central station ping acks and raises an alarm when the central station stops
+/// responding. Paused while the serial port is in maintenance mode.
+pub struct Watchdog {
+    config:         Config,
+    subscribe_once: Once,
+    /// Consecutive missed pings.
+    missed:         u32,
+    /// When the central station was declared unresponsive.
+    down_since:     Option<Instant>,
+    maintenance:    bool,
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl Watchdog {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            missed: 0,
+            down_since: None,
+            maintenance: false,
+        }
+    }
+
+    #[inline]
+    fn report(&self, report: Report) {
+        self.issue_system_async(ground::LinkReport(report));
+    }
+
+    fn record(&mut self, acked: bool) {
+        let missed = self.missed;
+        let tally = self.config.tally(missed, acked);
+        self.missed = tally.missed;
+
+        if !acked {
+            tracing::warn!(missed = tally.missed, "central station missed ping");
+        }
+
+        if tally.unresponsive {
+            tracing::error!(missed = tally.missed, "central station unresponsive");
+
+            self.down_since = Some(Instant::now());
+            self.report(Report::CsUnresponsive {
+                missed:       tally.missed,
+                reconnecting: self.config.reconnect,
+            });
+        }
+
+        if tally.reconnect {
+            tracing::warn!("reconnecting serial port");
+            self.issue_system_async(raw::Reconnect);
+        }
+
+        if tally.power_cycle {
+            tracing::error!(missed = tally.missed, "requesting central station power cycle");
+
+            self.report(Report::CsPowerCycleRequested {
+                missed: tally.missed,
+            });
+        }
+
+        if tally.recovered {
+            tracing::info!(missed, "central station recovered");
+
+            let since = self.down_since.take().unwrap_or_else(Instant::now);
+            self.report(Report::CsRecovered {
+                missed,
+                down_for: since.elapsed(),
+            });
+        }
+    }
+}
+
+impl Actor for Watchdog {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, PingResult>(ctx);
+            self.subscribe_async::<SystemBroker, raw::Maintenance>(ctx);
+        });
+
+        tracing::info!("central station watchdog started");
+    }
+}
+
+impl Supervised for Watchdog {}
+
+impl Handler<PingResult> for Watchdog {
+    type Result = ();
+
+    #[tracing::instrument(skip_all, fields(acked = msg.acked))]
+    fn handle(&mut self, msg: PingResult, _ctx: &mut Self::Context) -> Self::Result {
+        if self.maintenance {
+            tracing::debug!("ignoring ping result in maintenance mode");
+            return;
+        }
+
+        self.record(msg.acked);
+    }
+}
+
+impl Handler<raw::Maintenance> for Watchdog {
+    type Result = ();
+
+    fn handle(&mut self, msg: raw::Maintenance, _ctx: &mut Self::Context) -> Self::Result {
+        self.maintenance = msg.0.is_some();
+        self.missed = 0;
+        self.down_since = None;
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use message::{
+    header::Event,
+    MissionEpoch,
+};
+
+use crate::state_machine::State;
+
+/// The persisted portion of the state machine: enough to pick up where we left off after the
+/// process restarts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Entry {
+    pub state:           State,
+    pub pending_evt:     Option<Event>,
+    pub transitioned_at: MissionEpoch,
+}
+
+impl Entry {
+    /// Time since the last transition. `MissionEpoch` wraps, so this is only meaningful for
+    /// intervals shorter than ~50 days.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        elapsed(self.transitioned_at)
+    }
+}
+
+#[inline]
+pub fn elapsed(since: MissionEpoch) -> Duration {
+    let now = u32::from(MissionEpoch::now());
+
+    Duration::from_millis(now.wrapping_sub(u32::from(since)) as u64)
+}
+
+/// The state machine's on-disk journal.
+pub type Journal = crate::journal::Journal<Entry>;
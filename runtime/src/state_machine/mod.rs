@@ -0,0 +1,703 @@
+use std::{
+    collections::{
+        BTreeSet,
+        VecDeque,
+    },
+    path::PathBuf,
+    sync::Once,
+    time::Duration,
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    Broker,
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+
+use message::{
+    downlink::{
+        ant_op,
+        state_machine::{
+            Handled,
+            Report,
+            Status,
+            Transition,
+        },
+    },
+    header::{
+        Destination,
+        Event,
+    },
+    payload::log::Type,
+    MissionEpoch,
+};
+
+use crate::{
+    dump,
+    ground,
+    limits,
+    params,
+    serial,
+    serial::{
+        send,
+        watchdog,
+    },
+    state_machine::{
+        journal::Journal,
+        table::{
+            Action,
+            Command,
+            Op,
+            Table,
+        },
+        timing::Timing,
+    },
+};
+
+pub(crate) mod journal;
+pub mod table;
+pub mod timing;
+
+pub use message::downlink::state_machine::State;
+
+/// Run `msg` as an ant op on behalf of another actor (e.g. a scheduled command that came due, or a
+/// thermostat heater command). It's gated on the current state and reported exactly like an op
+/// uplinked from the ground.
+#[derive(Clone, Debug, PartialEq, Message)]
+#[rtype(result = "()")]
+pub struct RunOp(pub message::Message);
+
+/// Number of transitions retained for status reports.
+const HISTORY_LEN: usize = 16;
+
+/// A background task started by an [`Action`].
+struct Task {
+    id:     u64,
+    handle: SpawnHandle,
+    desc:   String,
+}
+
+pub struct StateMachine {
+    state:           State,
+    tasks:           Vec<Task>,
+    next_task:       u64,
+    subscribe_once:  Once,
+    pending_evt:     Option<Event>,
+    transitioned_at: MissionEpoch,
+    journal:         Option<Journal>,
+    table:           Table,
+    timing:          Timing,
+    history:         VecDeque<Transition>,
+    /// Ant operations awaiting an ack.
+    running_ops:     BTreeSet<Event>,
+}
+
+impl Default for StateMachine {
+    fn default() -> Self {
+        Self {
+            state:           State::INITIAL,
+            tasks:           vec![],
+            next_task:       0,
+            subscribe_once:  Once::new(),
+            pending_evt:     None,
+            transitioned_at: MissionEpoch::now(),
+            journal:         None,
+            table:           Table::default(),
+            timing:          Timing::default(),
+            history:         VecDeque::with_capacity(HISTORY_LEN),
+            running_ops:     BTreeSet::new(),
+        }
+    }
+}
+
+#[inline]
+async fn build_command(command: Command) -> message::Message {
+    message::command(&params().await, command.destination, command.event)
+}
+
+impl StateMachine {
+    /// Construct a state machine driven by `table` (with delays resolved against `timing`) that
+    /// persists its state to the journal at `journal_path` (if provided), resuming from the last
+    /// journaled state if one exists.
+    pub fn new(journal_path: Option<PathBuf>, table: Table, timing: Timing) -> Self {
+        let mut result = Self {
+            table,
+            timing,
+            ..Default::default()
+        };
+
+        let Some(journal) = journal_path.map(Journal::new) else {
+            return result;
+        };
+
+        match journal.load() {
+            Ok(Some(entry)) => {
+                tracing::info!(state = ?entry.state, pending_evt = ?entry.pending_evt, elapsed = ?entry.elapsed(), "resuming from state journal");
+
+                result.state = entry.state;
+                result.pending_evt = entry.pending_evt;
+                result.transitioned_at = entry.transitioned_at;
+            },
+            Ok(None) => tracing::info!("no state journal present, starting fresh"),
+            Err(e) => tracing::error!(error = %e, "loading state journal, starting fresh"),
+        }
+
+        result.journal = Some(journal);
+        result
+    }
+
+    fn persist(&self) {
+        let Some(ref journal) = self.journal else {
+            return;
+        };
+
+        let entry = journal::Entry {
+            state:           self.state,
+            pending_evt:     self.pending_evt,
+            transitioned_at: self.transitioned_at,
+        };
+
+        if let Err(e) = journal.store(&entry) {
+            tracing::error!(error = %e, "writing state journal");
+        }
+    }
+
+    /// Move to `state` outside of [`StateMachine::step`] (i.e. from a deferred task). Any other
+    /// running tasks belonged to the old state and are cancelled.
+    fn transition(&mut self, state: State, ctx: &mut Context<Self>) {
+        if self.state == state {
+            return;
+        }
+
+        tracing::info!(old_state = ?self.state, new_state = ?state, "state machine transition");
+        self.cancel_tasks(ctx);
+
+        let from = self.state;
+        self.state = state;
+        self.record(from, None);
+        self.persist();
+    }
+
+    /// Note that we just moved from `from` to the current state.
+    fn record(&mut self, from: State, event: Option<Event>) {
+        self.transitioned_at = MissionEpoch::now();
+
+        if self.history.len() >= HISTORY_LEN {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(Transition {
+            at: self.transitioned_at,
+            from,
+            to: self.state,
+            event,
+        });
+    }
+
+    #[inline]
+    fn task_id(&mut self) -> u64 {
+        self.next_task = self.next_task.wrapping_add(1);
+        self.next_task
+    }
+
+    #[inline]
+    fn add_task(&mut self, id: u64, handle: SpawnHandle, action: &Action) {
+        self.tasks.push(Task {
+            id,
+            handle,
+            desc: action.to_string(),
+        });
+    }
+
+    #[inline]
+    fn finish_task(&mut self, id: u64) {
+        self.tasks.retain(|t| t.id != id);
+    }
+
+    fn cancel_tasks(&mut self, ctx: &mut Context<Self>) {
+        for task in self.tasks.drain(..) {
+            ctx.cancel_future(task.handle);
+        }
+    }
+
+    fn status(&self) -> Status {
+        Status {
+            state:         self.state,
+            pending_evt:   self.pending_evt,
+            running_tasks: self.tasks.iter().map(|t| t.desc.clone()).collect(),
+            time_in_state: journal::elapsed(self.transitioned_at),
+            history:       self.history.iter().copied().collect(),
+        }
+    }
+
+    /// Run `action`, returning whether the previously-running tasks should be retained. `elapsed`
+    /// is subtracted from a top-level `Later` delay (used when resuming partway through a state).
+    fn apply(&mut self, action: &Action, elapsed: Duration, ctx: &mut Context<Self>) -> bool {
+        match action.clone() {
+            Action::Retain => return true,
+
+            Action::Log {
+                message,
+            } => tracing::info!("{message}"),
+
+            Action::Send {
+                command,
+            } => {
+                ctx.spawn(fut::wrap_future(async move {
+                    serial::do_send(build_command(command).await).await;
+                }));
+            },
+
+            Action::Ping {
+                command,
+                timeout,
+            } => {
+                let timeout = self.timing.delay(timeout);
+
+                let fut = fut::wrap_future(async move {
+                    send(build_command(command).await, Some(timeout)).await
+                })
+                .map(move |result, act: &mut Self, _ctx| {
+                    if let Err(ref e) = result {
+                        tracing::warn!(error = %e, ?command, "ping not acked");
+                    }
+
+                    act.issue_system_async(watchdog::PingResult {
+                        acked: result.is_ok(),
+                    });
+                });
+
+                ctx.spawn(fut);
+            },
+
+            Action::Request {
+                command,
+                timeout,
+            } => {
+                let timeout = self.timing.delay(timeout);
+
+                ctx.wait(fut::wrap_future(async move {
+                    if let Err(e) = send(build_command(command).await, Some(timeout)).await {
+                        tracing::error!(error = %e, ?command, "sending command");
+                    }
+                }));
+            },
+
+            Action::SendRetry {
+                command,
+                retry,
+                on_success,
+                then,
+                on_failure,
+            } => {
+                let retry = self.timing.retry(retry);
+                let id = self.task_id();
+
+                let fut = fut::wrap_future(serial::send_retry(
+                    move || Box::pin(build_command(command)),
+                    retry.timeout,
+                    retry.strategy(),
+                ))
+                .map(move |result, act: &mut Self, ctx| {
+                    act.finish_task(id);
+
+                    act.issue_system_async(ground::StateMachineReport(Report::Command {
+                        event: command.event,
+                        acked: result.is_ok(),
+                    }));
+
+                    if let Err(e) = result {
+                        tracing::error!(error = %e, ?command, "sending command with retry");
+
+                        for action in &on_failure {
+                            act.apply(action, Duration::ZERO, ctx);
+                        }
+
+                        return;
+                    }
+
+                    if let Some(state) = on_success {
+                        act.transition(state, ctx);
+                    }
+
+                    for action in &then {
+                        act.apply(action, Duration::ZERO, ctx);
+                    }
+                });
+
+                let handle = ctx.spawn(fut);
+                self.add_task(id, handle, action);
+            },
+
+            Action::Interval {
+                period,
+                actions,
+            } => {
+                let id = self.task_id();
+
+                let handle = ctx.run_interval(self.timing.delay(period), move |a, ctx| {
+                    for action in &actions {
+                        a.apply(action, Duration::ZERO, ctx);
+                    }
+                });
+
+                self.add_task(id, handle, action);
+            },
+
+            Action::Later {
+                delay,
+                to,
+                actions,
+            } => {
+                let delay = self.timing.delay(delay).saturating_sub(elapsed);
+                let id = self.task_id();
+
+                let handle = ctx.run_later(delay, move |a, ctx| {
+                    a.finish_task(id);
+
+                    if let Some(state) = to {
+                        a.transition(state, ctx);
+                    }
+
+                    for action in &actions {
+                        a.apply(action, Duration::ZERO, ctx);
+                    }
+                });
+
+                self.add_task(id, handle, action);
+            },
+
+            Action::Alarm {
+                alarm,
+            } => {
+                tracing::error!(%alarm, "state machine alarm");
+                self.issue_system_async(ground::StateMachineReport(Report::Alarm(alarm)));
+            },
+
+            Action::QueueEvent {
+                event,
+            } => self.pending_evt = Some(event),
+
+            Action::ClearPending => self.pending_evt = None,
+
+            Action::ReplayPending => {
+                if let Some(evt) = self.pending_evt.take() {
+                    tracing::info!(?evt, "replaying queued event");
+
+                    self.persist();
+                    ctx.address().do_send(EventWrap(evt));
+                }
+            },
+
+            Action::Exit => {
+                tracing::warn!("received restart, exiting");
+                ground::downlink::lifecycle(Type::Shutdown);
+
+                // not a task of ours: nothing may cancel it
+                actix::spawn(async {
+                    tokio::time::sleep(ground::downlink::SHUTDOWN_GRACE).await;
+                    std::process::exit(1);
+                });
+            },
+        }
+
+        false
+    }
+
+    /// Re-arm the timers belonging to the current state. Used when resuming from the journal or
+    /// after a supervisor restart, either of which drops any running tasks.
+    #[tracing::instrument(skip_all, fields(state = ?self.state))]
+    fn resume(&mut self, ctx: &mut Context<Self>) {
+        let actions = self.table.resume_actions(self.state).to_vec();
+
+        if actions.is_empty() {
+            return;
+        }
+
+        let elapsed = journal::elapsed(self.transitioned_at);
+
+        for action in &actions {
+            self.apply(action, elapsed, ctx);
+        }
+
+        tracing::info!(?elapsed, "re-armed state timers");
+    }
+
+    #[tracing::instrument(skip_all, fields(state = ?self.state, event = ?event))]
+    fn step(&mut self, event: Event, ctx: &mut Context<Self>) -> Result<(), serial::Error> {
+        let old_tasks = std::mem::take(&mut self.tasks);
+
+        tracing::info!("state machine event");
+        let init_state = self.state;
+        let init_pending = self.pending_evt;
+
+        let Some(transition) = self.table.lookup(init_state, init_pending, event).cloned() else {
+            tracing::debug!(state = ?init_state, ?event, "unmatched state machine transition");
+            self.tasks = old_tasks;
+
+            return Ok(());
+        };
+
+        if let Some(to) = transition.to {
+            self.state = to;
+        }
+
+        let mut retain = false;
+        for action in &transition.actions {
+            retain |= self.apply(action, Duration::ZERO, ctx);
+        }
+
+        if retain {
+            self.tasks.splice(0..0, old_tasks);
+        } else {
+            old_tasks.into_iter().for_each(|t| {
+                ctx.cancel_future(t.handle);
+            });
+        }
+
+        if self.state != init_state {
+            tracing::info!(new_state = ?self.state, "state machine transition");
+            self.record(init_state, Some(event));
+        } else {
+            tracing::info!("state machine did not transition");
+        }
+
+        if self.state != init_state || self.pending_evt != init_pending {
+            self.persist();
+        }
+
+        if transition.report {
+            self.issue_system_async(ground::StateMachineReport(Report::Handled(Handled {
+                event,
+                from: init_state,
+                to: self.state,
+                pending_evt: self.pending_evt,
+                actions: transition.actions.iter().map(|a| a.to_string()).collect(),
+            })));
+        }
+
+        Ok(())
+    }
+
+    /// Send the ground command `msg` as ant operation `op` if it's allowed in the current state,
+    /// reporting the outcome on the downlink.
+    #[tracing::instrument(skip_all, fields(event = ?op.event, state = ?self.state))]
+    fn run_op(&mut self, op: Op, msg: message::Message, ctx: &mut Context<Self>) {
+        let event = op.event;
+
+        let report = |report| Broker::<SystemBroker>::issue_async(ground::AntOpReport(report));
+
+        if !op.allowed_in(self.state) {
+            tracing::warn!("ant op not allowed in current state");
+
+            return report(ant_op::Report::Rejected {
+                event,
+                state: self.state,
+            });
+        }
+
+        if !self.running_ops.insert(event) {
+            tracing::warn!("ant op already in progress");
+            return report(ant_op::Report::Busy(event));
+        }
+
+        let retry = self.timing.retry(op.retry);
+
+        let fut = fut::wrap_future(serial::send_retry(
+            move || {
+                let msg = msg.clone();
+                Box::pin(async move { msg })
+            },
+            retry.timeout,
+            retry.strategy(),
+        ))
+        .map(move |result, act: &mut Self, _ctx| {
+            act.running_ops.remove(&event);
+
+            let r = match result {
+                Ok(serial::Response::Ant(pkt)) => ant_op::Report::Acked {
+                    event,
+                    ack: ant_op::Ack::Ant(pkt.payload),
+                },
+                Ok(serial::Response::Message(_)) => ant_op::Report::Acked {
+                    event,
+                    ack: ant_op::Ack::CentralStation,
+                },
+                Err(e) => {
+                    tracing::error!(error = %e, ?event, "ant op failed");
+
+                    ant_op::Report::Failed {
+                        event,
+                        error: e.to_string(),
+                    }
+                },
+            };
+
+            report(r);
+        });
+
+        ctx.spawn(fut);
+    }
+
+    #[tracing::instrument(skip_all)]
+    fn do_step(&mut self, event: Event, ctx: &mut Context<Self>) {
+        if let Err(e) = self.step(event, ctx) {
+            tracing::error!(error = %e, "advancing state machine");
+        }
+    }
+}
+
+impl Actor for StateMachine {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, ground::UpCommand>(ctx);
+            self.subscribe_async::<SystemBroker, serial::DownMessage>(ctx);
+            self.subscribe_async::<SystemBroker, ground::uplink::Liveness>(ctx);
+            self.subscribe_async::<SystemBroker, limits::Trip>(ctx);
+            self.subscribe_async::<SystemBroker, dump::DumpComplete>(ctx);
+            self.subscribe_async::<SystemBroker, RunOp>(ctx);
+        });
+
+        self.resume(ctx);
+
+        tracing::info!("state machine controller started");
+    }
+
+    fn stopped(&mut self, _ctx: &mut Self::Context) {
+        tracing::error!("state machine controller stopped");
+    }
+}
+
+impl Handler<ground::UpCommand> for StateMachine {
+    type Result = MessageResult<ground::UpCommand>;
+
+    #[tracing::instrument(skip_all, fields(%msg))]
+    fn handle(
+        &mut self,
+        ground::UpCommand(msg): ground::UpCommand,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let hdr = msg.header.header;
+
+        if hdr.ty.event == Event::FEStatus {
+            tracing::info!("reporting status");
+            self.issue_system_async(ground::StateMachineReport(Report::Status(self.status())));
+
+            return MessageResult(());
+        }
+
+        self.do_step(hdr.ty.event, ctx);
+
+        if let Some(op) = self.table.op(hdr.ty.event).cloned() {
+            self.run_op(op, msg, ctx);
+        } else if matches!(hdr.destination, Destination::Ant | Destination::CentralStation) {
+            // forward any other messages for the ant or the cs from the ground
+            ctx.spawn(fut::wrap_future(serial::do_send(msg)));
+        }
+
+        MessageResult(())
+    }
+}
+
+impl Handler<serial::DownMessage> for StateMachine {
+    type Result = MessageResult<serial::DownMessage>;
+
+    #[inline]
+    #[tracing::instrument(skip_all)]
+    fn handle(
+        &mut self,
+        serial::DownMessage(msg): serial::DownMessage,
+        ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let evt = msg.header.header.ty.event;
+
+        if !matches!(evt, Event::CSBLEConnect | Event::CSBLEDisconnect) {
+            tracing::trace!("state machine ignoring non-BLE event");
+            return MessageResult(());
+        }
+
+        self.do_step(evt, ctx);
+
+        MessageResult(())
+    }
+}
+
+impl Handler<ground::uplink::Liveness> for StateMachine {
+    type Result = ();
+
+    #[tracing::instrument(skip_all, fields(alive = msg.alive))]
+    fn handle(&mut self, msg: ground::uplink::Liveness, ctx: &mut Self::Context) -> Self::Result {
+        let event = if msg.alive {
+            Event::FEUplinkResumed
+        } else {
+            Event::FEUplinkLost
+        };
+
+        self.do_step(event, ctx);
+    }
+}
+
+impl Handler<limits::Trip> for StateMachine {
+    type Result = ();
+
+    #[tracing::instrument(skip_all, fields(field = %msg.field))]
+    fn handle(&mut self, msg: limits::Trip, ctx: &mut Self::Context) -> Self::Result {
+        self.do_step(Event::FELimitTrip, ctx);
+    }
+}
+
+impl Handler<dump::DumpComplete> for StateMachine {
+    type Result = ();
+
+    #[tracing::instrument(skip_all, fields(trigger = %msg.0.trigger))]
+    fn handle(&mut self, msg: dump::DumpComplete, ctx: &mut Self::Context) -> Self::Result {
+        self.do_step(Event::FEDumpComplete, ctx);
+    }
+}
+
+impl Handler<RunOp> for StateMachine {
+    type Result = ();
+
+    #[tracing::instrument(skip_all, fields(%msg))]
+    fn handle(&mut self, RunOp(msg): RunOp, ctx: &mut Self::Context) -> Self::Result {
+        let event = msg.header.header.ty.event;
+
+        let Some(op) = self.table.op(event).cloned() else {
+            tracing::warn!(?event, "command is not an ant op");
+            return;
+        };
+
+        self.do_step(event, ctx);
+        self.run_op(op, msg, ctx);
+    }
+}
+
+#[derive(Message)]
+#[rtype("()")]
+struct EventWrap(Event);
+
+impl Handler<EventWrap> for StateMachine {
+    type Result = ();
+
+    #[inline]
+    #[tracing::instrument(skip_all)]
+    fn handle(&mut self, EventWrap(event): EventWrap, ctx: &mut Self::Context) -> Self::Result {
+        self.do_step(event, ctx);
+    }
+}
+
+impl Supervised for StateMachine {
+    fn restarting(&mut self, _ctx: &mut <Self as Actor>::Context) {
+        tracing::warn!("state machine controller restarting");
+
+        // tasks and in-flight ops ran on the old context and died with it: `started` re-arms the
+        // state's timers
+        self.tasks.clear();
+        self.running_ops.clear();
+    }
+}
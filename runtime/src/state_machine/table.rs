@@ -0,0 +1,869 @@
+use std::{
+    collections::{
+        BTreeSet,
+        HashSet,
+    },
+    fmt::{
+        Display,
+        Formatter,
+    },
+    fs,
+    io,
+    path::Path,
+    time::Duration,
+};
+
+use message::header::{
+    Destination,
+    Event,
+};
+
+use message::downlink::state_machine::Alarm;
+
+use crate::{
+    config,
+    state_machine::{
+        timing::{
+            Delay,
+            RetryPolicy,
+        },
+        State,
+    },
+};
+
+/// A message sent to the central station or the ant.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Command {
+    pub destination: Destination,
+    pub event:       Event,
+}
+
+impl Command {
+    #[inline]
+    pub const fn cs(event: Event) -> Self {
+        Self {
+            destination: Destination::CentralStation,
+            event,
+        }
+    }
+
+    #[inline]
+    pub const fn ant(event: Event) -> Self {
+        Self {
+            destination: Destination::Ant,
+            event,
+        }
+    }
+}
+
+impl Display for Command {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} -> {:?}", self.event, self.destination)
+    }
+}
+
+/// Parameters for an acked send with jittered exponential backoff.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Retry {
+    /// How long to wait for an ack to each attempt.
+    #[serde(with = "crate::serde_millis")]
+    pub timeout:   Duration,
+    #[serde(with = "crate::serde_millis")]
+    pub base:      Duration,
+    #[serde(with = "crate::serde_millis")]
+    pub max_delay: Duration,
+    pub count:     usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Action {
+    /// Keep whatever tasks were running before this event (by default, running tasks are
+    /// cancelled by every matched transition).
+    Retain,
+
+    /// Emit a log line.
+    Log {
+        message: String,
+    },
+
+    /// Send a command without waiting for an ack.
+    Send {
+        command: Command,
+    },
+
+    /// Send a command in the background and report whether it was acked within `timeout` to the
+    /// central station watchdog.
+    Ping {
+        command: Command,
+        timeout: Delay,
+    },
+
+    /// Send a command and block the state machine until it's acked or `timeout` elapses.
+    Request {
+        command: Command,
+        timeout: Delay,
+    },
+
+    /// Send a command with retries in the background (starts a task). If it's acked, transition to
+    /// `on_success` and run `then`; if it never is, run `on_failure`.
+    ///
+    /// The outcome is reported on the downlink.
+    SendRetry {
+        command:    Command,
+        retry:      RetryPolicy,
+        #[serde(default)]
+        on_success: Option<State>,
+        #[serde(default)]
+        then:       Vec<Action>,
+        #[serde(default)]
+        on_failure: Vec<Action>,
+    },
+
+    /// Raise an alarm on the downlink.
+    Alarm {
+        alarm: Alarm,
+    },
+
+    /// Run `actions` every `period` (starts a task).
+    Interval {
+        period:  Delay,
+        actions: Vec<Action>,
+    },
+
+    /// After `delay`, transition to `to` (if provided), then run `actions` (starts a task).
+    Later {
+        delay:   Delay,
+        #[serde(default)]
+        to:      Option<State>,
+        #[serde(default)]
+        actions: Vec<Action>,
+    },
+
+    /// Store `event` to be replayed later.
+    QueueEvent {
+        event: Event,
+    },
+
+    /// Drop the queued event, if any.
+    ClearPending,
+
+    /// Feed the queued event, if any, back into the state machine.
+    ReplayPending,
+
+    /// Exit the process (to be restarted by the supervisor), once the shutdown lifecycle event has
+    /// had time to be downlinked.
+    Exit,
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let list = |actions: &[Action]| {
+            actions.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+        };
+
+        match self {
+            Action::Retain => write!(f, "retain"),
+            Action::Log {
+                message,
+            } => write!(f, "log {message:?}"),
+            Action::Send {
+                command,
+            } => write!(f, "send {command}"),
+            Action::Ping {
+                command,
+                ..
+            } => write!(f, "ping {command}"),
+            Action::Request {
+                command,
+                ..
+            } => write!(f, "request {command}"),
+            Action::SendRetry {
+                command,
+                on_success,
+                then,
+                on_failure,
+                ..
+            } => {
+                write!(f, "send {command} with retry")?;
+
+                if let Some(state) = on_success {
+                    write!(f, ", then {state}")?;
+                }
+
+                if !then.is_empty() {
+                    write!(f, ", then [{}]", list(then))?;
+                }
+
+                if !on_failure.is_empty() {
+                    write!(f, ", else [{}]", list(on_failure))?;
+                }
+
+                Ok(())
+            },
+            Action::Alarm {
+                alarm,
+            } => write!(f, "alarm {alarm:?}"),
+            Action::Interval {
+                period,
+                actions,
+            } => write!(f, "every {period:?}: [{}]", list(actions)),
+            Action::Later {
+                delay,
+                to,
+                actions,
+            } => {
+                write!(f, "after {delay:?}: ")?;
+
+                if let Some(state) = to {
+                    write!(f, "{state}, ")?;
+                }
+
+                write!(f, "[{}]", list(actions))
+            },
+            Action::QueueEvent {
+                event,
+            } => write!(f, "queue {event:?}"),
+            Action::ClearPending => write!(f, "clear pending"),
+            Action::ReplayPending => write!(f, "replay pending"),
+            Action::Exit => write!(f, "exit"),
+        }
+    }
+}
+
+impl Action {
+    /// Whether this action starts a background task.
+    #[inline]
+    pub fn is_task(&self) -> bool {
+        matches!(self, Action::SendRetry { .. } | Action::Interval { .. } | Action::Later { .. })
+    }
+
+    /// Whether this action may transition the state machine once it completes. A deferred
+    /// transition cancels every other running task.
+    #[inline]
+    fn is_deferred_transition(&self) -> bool {
+        matches!(
+            self,
+            Action::SendRetry {
+                on_success: Some(_),
+                ..
+            } | Action::Later {
+                to: Some(_),
+                ..
+            }
+        )
+    }
+
+    fn targets(&self, out: &mut BTreeSet<State>) {
+        match self {
+            Action::SendRetry {
+                on_success,
+                then,
+                on_failure,
+                ..
+            } => {
+                out.extend(on_success);
+                then.iter().chain(on_failure).for_each(|a| a.targets(out));
+            },
+
+            Action::Later {
+                to,
+                actions,
+                ..
+            } => {
+                out.extend(to);
+                actions.iter().for_each(|a| a.targets(out));
+            },
+
+            Action::Interval {
+                actions,
+                ..
+            } => actions.iter().for_each(|a| a.targets(out)),
+
+            _ => {},
+        }
+    }
+}
+
+/// Additional precondition on a transition beyond the current state and the incoming event.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Guard {
+    /// The given event is queued.
+    Pending(Event),
+
+    /// No event is queued.
+    NoPending,
+}
+
+impl Guard {
+    #[inline]
+    pub fn check(&self, pending: Option<Event>) -> bool {
+        match self {
+            Guard::Pending(evt) => pending == Some(*evt),
+            Guard::NoPending => pending.is_none(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Transition {
+    /// State this transition applies to. `None` matches any state, at lower precedence than a
+    /// transition naming the state explicitly.
+    #[serde(default)]
+    pub from:    Option<State>,
+    pub event:   Event,
+    /// Guarded transitions take precedence over unguarded ones.
+    #[serde(default)]
+    pub guard:   Option<Guard>,
+    /// State to move to immediately. `None` stays in the current state.
+    #[serde(default)]
+    pub to:      Option<State>,
+    #[serde(default)]
+    pub actions: Vec<Action>,
+    /// Report on the downlink when this transition is taken.
+    #[serde(default)]
+    pub report:  bool,
+}
+
+impl Transition {
+    #[inline]
+    fn matches(&self, state: Option<State>, pending: Option<Event>, event: Event) -> bool {
+        self.event == event && self.from == state && self.guard.map_or(true, |g| g.check(pending))
+    }
+}
+
+/// Timers to re-arm when the state machine resumes in `state` (after a restart).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Resume {
+    pub state:   State,
+    pub actions: Vec<Action>,
+}
+
+/// A ground-initiated ant command that's sent with retries and tracked to completion rather than
+/// forwarded blindly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub struct Op {
+    pub event:   Event,
+    /// States in which the command may be sent. It's rejected in any other state.
+    pub allowed: BTreeSet<State>,
+    pub retry:   RetryPolicy,
+}
+
+impl Op {
+    #[inline]
+    pub fn allowed_in(&self, state: State) -> bool {
+        self.allowed.contains(&state)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Table {
+    pub transitions: Vec<Transition>,
+    #[serde(default)]
+    pub resume:      Vec<Resume>,
+    /// Ant operations. Ground commands for events not listed here are forwarded as-is.
+    #[serde(default)]
+    pub ops:         Vec<Op>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Format(#[from] serde_json::Error),
+
+    #[error("ambiguous transitions for {event:?} from {from:?} (guard {guard:?})")]
+    Ambiguous {
+        from:  Option<State>,
+        event: Event,
+        guard: Option<Guard>,
+    },
+
+    #[error("state {0:?} is unreachable from the initial state")]
+    Unreachable(State),
+
+    #[error("more than one deferred transition in a single action list for {0:?}")]
+    ConflictingTransitions(Event),
+
+    #[error("more than one resume entry for {0:?}")]
+    DuplicateResume(State),
+
+    #[error("more than one op entry for {0:?}")]
+    DuplicateOp(Event),
+
+    #[error("interval period for {0:?} must be nonzero")]
+    ZeroInterval(Event),
+
+    #[error(transparent)]
+    Timing(#[from] config::Error),
+}
+
+impl Table {
+    /// Load a table from a JSON file and validate it.
+    #[tracing::instrument(skip(path), fields(path = %path.as_ref().display()), err(Display))]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let data = fs::read(path.as_ref())?;
+        let table: Table = serde_json::from_slice(&data)?;
+
+        table.validate()?;
+
+        Ok(table)
+    }
+
+    /// Find the transition that applies in `state` (with `pending` queued) for `event`.
+    ///
+    /// Precedence: an explicit `from` state beats a wildcard, and a guarded transition beats an
+    /// unguarded one.
+    pub fn lookup(
+        &self,
+        state: State,
+        pending: Option<Event>,
+        event: Event,
+    ) -> Option<&Transition> {
+        let find = |from: Option<State>, guarded: bool| {
+            self.transitions
+                .iter()
+                .find(|t| t.guard.is_some() == guarded && t.matches(from, pending, event))
+        };
+
+        find(Some(state), true)
+            .or_else(|| find(Some(state), false))
+            .or_else(|| find(None, true))
+            .or_else(|| find(None, false))
+    }
+
+    #[inline]
+    pub fn op(&self, event: Event) -> Option<&Op> {
+        self.ops.iter().find(|op| op.event == event)
+    }
+
+    #[inline]
+    pub fn resume_actions(&self, state: State) -> &[Action] {
+        self.resume
+            .iter()
+            .find(|r| r.state == state)
+            .map(|r| r.actions.as_slice())
+            .unwrap_or_default()
+    }
+
+    pub fn validate(&self) -> Result<(), Error> {
+        let mut seen = HashSet::new();
+
+        for t in &self.transitions {
+            if !seen.insert((t.from, t.event, t.guard)) {
+                return Err(Error::Ambiguous {
+                    from:  t.from,
+                    event: t.event,
+                    guard: t.guard,
+                });
+            }
+
+            check_actions(&t.actions, t.event)?;
+        }
+
+        let mut resumed = BTreeSet::new();
+        for r in &self.resume {
+            if !resumed.insert(r.state) {
+                return Err(Error::DuplicateResume(r.state));
+            }
+
+            check_actions(&r.actions, Event::FEPing)?;
+        }
+
+        let mut ops = HashSet::new();
+        for op in &self.ops {
+            if !ops.insert(op.event) {
+                return Err(Error::DuplicateOp(op.event));
+            }
+
+            if let RetryPolicy::Custom(retry) = op.retry {
+                retry.validate("op retry")?;
+            }
+        }
+
+        let reachable = self.reachable();
+
+        match State::ALL.iter().find(|s| !reachable.contains(s)) {
+            Some(&state) => Err(Error::Unreachable(state)),
+            None => Ok(()),
+        }
+    }
+
+    fn reachable(&self) -> BTreeSet<State> {
+        let mut reachable = BTreeSet::from([State::INITIAL]);
+
+        loop {
+            let mut next = reachable.clone();
+
+            for t in &self.transitions {
+                if t.from.map_or(false, |from| !reachable.contains(&from)) {
+                    continue;
+                }
+
+                next.extend(t.to);
+                t.actions.iter().for_each(|a| a.targets(&mut next));
+            }
+
+            if next.len() == reachable.len() {
+                return reachable;
+            }
+
+            reachable = next;
+        }
+    }
+}
+
+fn check_actions(actions: &[Action], event: Event) -> Result<(), Error> {
+    if actions.iter().filter(|a| a.is_deferred_transition()).count() > 1 {
+        return Err(Error::ConflictingTransitions(event));
+    }
+
+    actions.iter().try_for_each(|a| match a {
+        Action::Interval {
+            period: Delay::Millis(0),
+            ..
+        } => Err(Error::ZeroInterval(event)),
+
+        Action::SendRetry {
+            retry,
+            then,
+            on_failure,
+            ..
+        } => {
+            if let RetryPolicy::Custom(retry) = retry {
+                retry.validate("send_retry")?;
+            }
+
+            check_actions(then, event)?;
+            check_actions(on_failure, event)
+        },
+
+        Action::Interval {
+            actions,
+            ..
+        }
+        | Action::Later {
+            actions,
+            ..
+        } => check_actions(actions, event),
+
+        _ => Ok(()),
+    })
+}
+
+impl Default for Table {
+    /// The standard mission behavior.
+    fn default() -> Self {
+        let ping_cs = || Action::Interval {
+            period:  Delay::CsPingInterval,
+            actions: vec![Action::Ping {
+                command: Command::cs(Event::CSPing),
+                timeout: Delay::CsPingTimeout,
+            }],
+        };
+
+        let settle_ble = || Action::Later {
+            delay:   Delay::BleSettleDelay,
+            to:      Some(State::AntReady),
+            actions: vec![Action::ReplayPending],
+        };
+
+        let start_ant = || {
+            let send_start = || Action::Request {
+                command: Command::ant(Event::AntStart),
+                timeout: Delay::AntStartResend,
+            };
+
+            Action::Later {
+                delay:   Delay::AntStartDelay,
+                to:      None,
+                actions: vec![send_start(), Action::Interval {
+                    period:  Delay::AntStartResend,
+                    actions: vec![send_start()],
+                }],
+            }
+        };
+
+        let t = |from: Option<State>, event: Event, to: Option<State>, actions: Vec<Action>| {
+            Transition {
+                from,
+                event,
+                guard: None,
+                to,
+                actions,
+                report: false,
+            }
+        };
+
+        // the central station loses all of its state (garage, BLE link) when power-cycled: fall
+        // back to pinging it, re-opening the garage if it had already been opened
+        let cs_power_cycled = |from: State, actions: Vec<Action>| Transition {
+            from: Some(from),
+            event: Event::FECSPowerCycled,
+            guard: None,
+            to: Some(State::PingCentralStation),
+            actions,
+            report: true,
+        };
+
+        let reopen_garage = || Action::SendRetry {
+            command:    Command::cs(Event::CSGarageOpen),
+            retry:      RetryPolicy::GarageOpen,
+            on_success: Some(State::GarageOpen),
+            then:       vec![],
+            on_failure: vec![],
+        };
+
+        // the ant only counts as stopped (AntReady) once it acks AntStop. if it doesn't, keep
+        // trying harder, then raise the alarm, power it off, and have the central station drop the
+        // BLE link whether or not the power-off was acked. with the link dropped we're back to
+        // GarageOpen, and the ant is picked up again when it reconnects.
+        let stop_ant = || {
+            let drop_ble = || Action::SendRetry {
+                command:    Command::cs(Event::CSBLEDisconnect),
+                retry:      RetryPolicy::Escalation,
+                on_success: Some(State::GarageOpen),
+                then:       vec![],
+                on_failure: vec![],
+            };
+
+            Action::SendRetry {
+                command:    Command::ant(Event::AntStop),
+                retry:      RetryPolicy::AntStop,
+                on_success: Some(State::AntReady),
+                then:       vec![],
+                on_failure: vec![Action::SendRetry {
+                    command:    Command::ant(Event::AntStop),
+                    retry:      RetryPolicy::Escalation,
+                    on_success: Some(State::AntReady),
+                    then:       vec![],
+                    on_failure: vec![
+                        Action::Alarm {
+                            alarm: Alarm::StopUnconfirmed,
+                        },
+                        Action::SendRetry {
+                            command:    Command::ant(Event::AntPowerOff),
+                            retry:      RetryPolicy::Escalation,
+                            on_success: Some(State::BLEConnected),
+                            then:       vec![drop_ble()],
+                            on_failure: vec![drop_ble()],
+                        },
+                    ],
+                }],
+            }
+        };
+
+        let noop = || {
+            vec![
+                Action::Log {
+                    message: "noop event: already in target state".to_owned(),
+                },
+                Action::Retain,
+            ]
+        };
+
+        #[allow(unused_mut)]
+        let mut transitions = vec![
+            t(Some(State::PingCentralStation), Event::FEPowerSupplied, None, noop()),
+            t(None, Event::FEPowerSupplied, Some(State::PingCentralStation), vec![ping_cs()]),
+            t(Some(State::GarageOpen), Event::FEGarageOpen, None, noop()),
+            t(None, Event::FEGarageOpen, None, vec![reopen_garage()]),
+            t(None, Event::CSBLEDisconnect, Some(State::GarageOpen), vec![]),
+            t(Some(State::BLEConnected), Event::CSBLEConnect, None, noop()),
+            t(None, Event::CSBLEConnect, Some(State::BLEConnected), vec![settle_ble()]),
+            t(Some(State::AntRun), Event::FERoverStop, None, noop()),
+            t(Some(State::AntReady), Event::FERoverStop, Some(State::AntRun), vec![start_ant()]),
+            t(Some(State::AntRun), Event::FERoverMove, Some(State::AntStopping), vec![stop_ant()]),
+            t(Some(State::AntStopping), Event::FERoverStop, None, vec![
+                Action::Log {
+                    message: "ignoring rover stop: ant still stopping".to_owned(),
+                },
+                Action::Retain,
+            ]),
+            // the ant has finished dumping the data from its last start: start it again rather than
+            // waiting out the resend timer, which stays as a fallback
+            t(Some(State::AntRun), Event::FEDumpComplete, None, vec![start_ant()]),
+            t(None, Event::FEDumpComplete, None, vec![Action::Retain]),
+            t(None, Event::FERoverStop, None, vec![
+                Action::Log {
+                    message: "queueing rover stop for later".to_owned(),
+                },
+                Action::QueueEvent {
+                    event: Event::FERoverStop,
+                },
+                Action::Retain,
+            ]),
+            t(None, Event::FERoverMove, None, vec![
+                Action::Log {
+                    message: "clearing pending event".to_owned(),
+                },
+                Action::ClearPending,
+                Action::Retain,
+            ]),
+            Transition {
+                from:    Some(State::FlightIdle),
+                event:   Event::FECSPowerCycled,
+                guard:   None,
+                to:      None,
+                actions: vec![
+                    Action::Log {
+                        message: "central station power-cycled before power supplied".to_owned(),
+                    },
+                    Action::Retain,
+                ],
+                report:  true,
+            },
+            cs_power_cycled(State::PingCentralStation, vec![ping_cs()]),
+            cs_power_cycled(State::GarageOpen, vec![ping_cs(), reopen_garage()]),
+            cs_power_cycled(State::BLEConnected, vec![ping_cs(), reopen_garage()]),
+            cs_power_cycled(State::AntReady, vec![ping_cs(), reopen_garage()]),
+            // the rover was stopped: restart the ant once the link comes back
+            cs_power_cycled(State::AntRun, vec![
+                Action::QueueEvent {
+                    event: Event::FERoverStop,
+                },
+                ping_cs(),
+                reopen_garage(),
+            ]),
+            cs_power_cycled(State::AntStopping, vec![ping_cs(), reopen_garage()]),
+            // the rover went quiet (e.g. crashed) with the ant running: stop the ant rather than
+            // restarting it indefinitely, and don't restart it until the rover asks again
+            Transition {
+                from:    Some(State::AntRun),
+                event:   Event::FEUplinkLost,
+                guard:   None,
+                to:      Some(State::AntStopping),
+                actions: vec![stop_ant(), Action::ClearPending],
+                report:  true,
+            },
+            Transition {
+                from:    None,
+                event:   Event::FEUplinkLost,
+                guard:   None,
+                to:      None,
+                actions: vec![
+                    Action::Log {
+                        message: "uplink lost, nothing to safe".to_owned(),
+                    },
+                    Action::Retain,
+                ],
+                report:  true,
+            },
+            // the rover came back: the rover stop queued when it went quiet is stale (it may have
+            // moved since), so leave it to the rover to ask again
+            Transition {
+                from:    None,
+                event:   Event::FEUplinkResumed,
+                guard:   Some(Guard::Pending(Event::FERoverStop)),
+                to:      None,
+                actions: vec![Action::ClearPending, Action::Retain],
+                report:  true,
+            },
+            Transition {
+                from:    None,
+                event:   Event::FEUplinkResumed,
+                guard:   None,
+                to:      None,
+                actions: vec![Action::Retain],
+                report:  true,
+            },
+            // a telemetry limit has asked for the ant to be stopped
+            Transition {
+                from:    Some(State::AntRun),
+                event:   Event::FELimitTrip,
+                guard:   None,
+                to:      Some(State::AntStopping),
+                actions: vec![stop_ant()],
+                report:  true,
+            },
+            Transition {
+                from:    None,
+                event:   Event::FELimitTrip,
+                guard:   None,
+                to:      None,
+                actions: vec![
+                    Action::Log {
+                        message: "limit tripped, ant not running".to_owned(),
+                    },
+                    Action::Retain,
+                ],
+                report:  true,
+            },
+            // below: non-state-affecting commands
+            t(None, Event::FERestart, None, vec![Action::Exit]),
+            t(None, Event::AntPing, None, vec![
+                Action::Send {
+                    command: Command::ant(Event::AntPing),
+                },
+                Action::Retain,
+            ]),
+            t(None, Event::FEPing, None, vec![
+                Action::Log {
+                    message: "pong".to_owned(),
+                },
+                Action::Retain,
+            ]),
+        ];
+
+        #[cfg(debug_assertions)]
+        transitions.push(t(None, Event::DebugCSPing, None, vec![
+            Action::Send {
+                command: Command::cs(Event::CSPing),
+            },
+            Action::Retain,
+        ]));
+
+        let op = |event: Event, allowed: &[State]| Op {
+            event,
+            allowed: allowed.iter().copied().collect(),
+            retry: RetryPolicy::AntOp,
+        };
+
+        // the ant is only reachable once BLE is connected. it can only be driven around or
+        // calibrated while the rover is stopped, and can't be powered off mid-run.
+        let ops = vec![
+            op(Event::AntCalibrate, &[State::AntRun]),
+            op(Event::AntHeaterOn, &[
+                State::BLEConnected,
+                State::AntReady,
+                State::AntRun,
+                State::AntStopping,
+            ]),
+            op(Event::AntHeaterOff, &[
+                State::BLEConnected,
+                State::AntReady,
+                State::AntRun,
+                State::AntStopping,
+            ]),
+            op(Event::AntPowerOff, &[State::BLEConnected, State::AntReady]),
+            op(Event::AntMoveForward, &[State::AntRun]),
+            op(Event::AntMoveBackward, &[State::AntRun]),
+        ];
+
+        Self {
+            transitions,
+            ops,
+            resume: vec![
+                Resume {
+                    state:   State::PingCentralStation,
+                    actions: vec![ping_cs()],
+                },
+                Resume {
+                    state:   State::BLEConnected,
+                    actions: vec![settle_ble()],
+                },
+                Resume {
+                    state:   State::AntRun,
+                    actions: vec![start_ant()],
+                },
+                Resume {
+                    state:   State::AntStopping,
+                    actions: vec![stop_ant()],
+                },
+            ],
+        }
+    }
+}
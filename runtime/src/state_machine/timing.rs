@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use crate::{
+    config::Error,
+    state_machine::table::Retry,
+};
+
+/// Named timing parameters referenced by the transition table.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Timing {
+    /// Period between CSPings while waiting for the garage to open.
+    #[serde(with = "crate::serde_millis")]
+    pub cs_ping_interval: Duration,
+
+    /// How long to wait for a CSPing ack before counting the ping as missed.
+    #[serde(with = "crate::serde_millis")]
+    pub cs_ping_timeout: Duration,
+
+    /// Time to let the BLE link settle after it connects before we consider the ant ready.
+    #[serde(with = "crate::serde_millis")]
+    pub ble_settle_delay: Duration,
+
+    /// Delay between the rover stopping and the first AntStart.
+    #[serde(with = "crate::serde_millis")]
+    pub ant_start_delay: Duration,
+
+    /// Period at which AntStart is resent while the rover is stopped (also the AntStart ack
+    /// timeout).
+    #[serde(with = "crate::serde_millis")]
+    pub ant_start_resend: Duration,
+
+    pub garage_open_retry: Retry,
+    pub ant_stop_retry:    Retry,
+    /// Retry parameters for each step of escalating an AntStop that was never acked: AntStop
+    /// again, then AntPowerOff, then dropping the BLE link.
+    pub escalation_retry:  Retry,
+    /// Retry parameters for ground-initiated ant operations (calibration, heater, test commands).
+    pub ant_op_retry:      Retry,
+}
+
+impl Default for Timing {
+    fn default() -> Self {
+        Self {
+            cs_ping_interval:  Duration::from_secs(5),
+            cs_ping_timeout:   Duration::from_secs(3),
+            ble_settle_delay:  Duration::from_secs(60),
+            ant_start_delay:   Duration::from_secs(10),
+            ant_start_resend:  Duration::from_secs(30),
+            garage_open_retry: Retry {
+                timeout:   Duration::from_secs(3),
+                base:      Duration::from_secs(1),
+                max_delay: Duration::from_secs(5),
+                count:     3,
+            },
+            ant_stop_retry:    Retry {
+                timeout:   Duration::from_secs(20),
+                base:      Duration::from_secs(5),
+                max_delay: Duration::from_secs(10),
+                count:     3,
+            },
+            escalation_retry:  Retry {
+                timeout:   Duration::from_secs(5),
+                base:      Duration::from_millis(500),
+                max_delay: Duration::from_secs(2),
+                count:     5,
+            },
+            ant_op_retry:      Retry {
+                timeout:   Duration::from_secs(10),
+                base:      Duration::from_secs(2),
+                max_delay: Duration::from_secs(10),
+                count:     3,
+            },
+        }
+    }
+}
+
+impl Timing {
+    pub fn validate(&self) -> Result<(), Error> {
+        let nonzero = |name, dur: Duration| {
+            if dur.is_zero() {
+                return Err(Error {
+                    name,
+                    reason: "must be nonzero",
+                });
+            }
+
+            Ok(())
+        };
+
+        nonzero("cs_ping_interval", self.cs_ping_interval)?;
+        nonzero("cs_ping_timeout", self.cs_ping_timeout)?;
+        nonzero("ble_settle_delay", self.ble_settle_delay)?;
+        nonzero("ant_start_resend", self.ant_start_resend)?;
+
+        self.garage_open_retry.validate("garage_open_retry")?;
+        self.ant_stop_retry.validate("ant_stop_retry")?;
+        self.escalation_retry.validate("escalation_retry")?;
+        self.ant_op_retry.validate("ant_op_retry")?;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn delay(&self, delay: Delay) -> Duration {
+        match delay {
+            Delay::CsPingInterval => self.cs_ping_interval,
+            Delay::CsPingTimeout => self.cs_ping_timeout,
+            Delay::BleSettleDelay => self.ble_settle_delay,
+            Delay::AntStartDelay => self.ant_start_delay,
+            Delay::AntStartResend => self.ant_start_resend,
+            Delay::Millis(ms) => Duration::from_millis(ms),
+        }
+    }
+
+    #[inline]
+    pub fn retry(&self, retry: RetryPolicy) -> Retry {
+        match retry {
+            RetryPolicy::GarageOpen => self.garage_open_retry,
+            RetryPolicy::AntStop => self.ant_stop_retry,
+            RetryPolicy::Escalation => self.escalation_retry,
+            RetryPolicy::AntOp => self.ant_op_retry,
+            RetryPolicy::Custom(retry) => retry,
+        }
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            cs_ping_interval = ?self.cs_ping_interval,
+            cs_ping_timeout = ?self.cs_ping_timeout,
+            ble_settle_delay = ?self.ble_settle_delay,
+            ant_start_delay = ?self.ant_start_delay,
+            ant_start_resend = ?self.ant_start_resend,
+            garage_open_retry = ?self.garage_open_retry,
+            ant_stop_retry = ?self.ant_stop_retry,
+            escalation_retry = ?self.escalation_retry,
+            ant_op_retry = ?self.ant_op_retry,
+            "state machine timing"
+        );
+    }
+}
+
+impl Retry {
+    pub fn validate(&self, name: &'static str) -> Result<(), Error> {
+        let err = |reason| {
+            Err(Error {
+                name,
+                reason,
+            })
+        };
+
+        if self.timeout.is_zero() {
+            return err("timeout must be nonzero");
+        }
+
+        if self.count == 0 {
+            return err("count must be at least 1");
+        }
+
+        if self.base > self.max_delay {
+            return err("base must not exceed max_delay");
+        }
+
+        Ok(())
+    }
+
+    /// Jittered exponential backoff between attempts.
+    pub fn strategy(&self) -> impl Iterator<Item = Duration> {
+        // proportion of signal to be jittered, i.e. multiply the signal by a random sample in the
+        // range [1 - JITTER_FACTOR, 1 + JITTER_FACTOR]
+        const JITTER_FACTOR: f64 = 0.5;
+
+        tokio_retry::strategy::ExponentialBackoff::from_millis(self.base.as_millis() as u64)
+            .max_delay(self.max_delay)
+            .map(|dur| {
+                // make distribution even about 0, scale by factor, offset about 1
+                let jitter = (rand::random::<f64>() - 0.5) * JITTER_FACTOR * 2. + 1.;
+
+                dur.mul_f64(jitter)
+            })
+            .take(self.count)
+    }
+}
+
+/// A duration in the transition table: either one of the named [`Timing`] parameters or a literal
+/// value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Delay {
+    CsPingInterval,
+    CsPingTimeout,
+    BleSettleDelay,
+    AntStartDelay,
+    AntStartResend,
+    Millis(u64),
+}
+
+/// Retry parameters in the transition table: either one of the named [`Timing`] parameters or a
+/// literal value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RetryPolicy {
+    GarageOpen,
+    AntStop,
+    Escalation,
+    AntOp,
+    Custom(Retry),
+}
@@ -30,3 +30,22 @@ pub async fn params() -> message::Params {
         seq,
     }
 }
+
+/// Mark `seq` as used by an in-flight request: [`seq`] skips it until it's [released](release_seq).
+///
+/// Holds are counted by sequence number alone, whatever the timestamp the request was stamped with:
+/// a request that stays in flight (at most until its deadline) keeps its number out of circulation
+/// for every later timestamp, not just its own.
+#[inline]
+pub async fn hold_seq(seq: u8) {
+    OverrideRegistry::query::<seq_service::Hold, seq_service::SeqService>()
+        .await
+        .do_send(seq_service::Hold(seq));
+}
+
+#[inline]
+pub async fn release_seq(seq: u8) {
+    OverrideRegistry::query::<seq_service::Release, seq_service::SeqService>()
+        .await
+        .do_send(seq_service::Release(seq));
+}
@@ -9,8 +9,30 @@ use actix::prelude::*;
 #[rtype(result = "u8")]
 pub struct Request;
 
-#[derive(Default)]
-pub struct SeqService(AtomicU8);
+/// A request stamped with this sequence number is in flight: don't hand it out again until it's
+/// [`Release`]d.
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Hold(pub u8);
+
+#[derive(Message)]
+#[rtype(result = "()")]
+pub struct Release(pub u8);
+
+pub struct SeqService {
+    next: AtomicU8,
+    /// In-flight requests holding each sequence number.
+    held: [u32; 256],
+}
+
+impl Default for SeqService {
+    fn default() -> Self {
+        Self {
+            next: AtomicU8::new(0),
+            held: [0; 256],
+        }
+    }
+}
 
 impl Actor for SeqService {
     type Context = Context<Self>;
@@ -25,6 +47,37 @@ impl Handler<Request> for SeqService {
 
     #[inline]
     fn handle(&mut self, _msg: Request, _ctx: &mut Self::Context) -> Self::Result {
-        MessageResult(self.0.fetch_add(1, Ordering::SeqCst))
+        for _ in 0..self.held.len() {
+            let seq = self.next.fetch_add(1, Ordering::SeqCst);
+
+            if self.held[seq as usize] == 0 {
+                return MessageResult(seq);
+            }
+        }
+
+        // every value is in flight: nothing to be done but reuse one
+        let seq = self.next.fetch_add(1, Ordering::SeqCst);
+        tracing::warn!(seq, "all sequence numbers in flight, reusing");
+
+        MessageResult(seq)
+    }
+}
+
+impl Handler<Hold> for SeqService {
+    type Result = ();
+
+    #[inline]
+    fn handle(&mut self, Hold(seq): Hold, _ctx: &mut Self::Context) -> Self::Result {
+        self.held[seq as usize] += 1;
+    }
+}
+
+impl Handler<Release> for SeqService {
+    type Result = ();
+
+    #[inline]
+    fn handle(&mut self, Release(seq): Release, _ctx: &mut Self::Context) -> Self::Result {
+        let held = &mut self.held[seq as usize];
+        *held = held.saturating_sub(1);
     }
 }
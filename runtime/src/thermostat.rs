@@ -0,0 +1,325 @@
+use std::{
+    sync::Once,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+
+use message::{
+    downlink::{
+        ant_op,
+        thermostat::Report,
+    },
+    header::{
+        Destination,
+        Event,
+    },
+};
+
+use crate::{
+    config,
+    ground,
+    params,
+    serial,
+    RunOp,
+};
+
+/// Ant temperature the thermostat regulates.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sensor {
+    BatteryTemp,
+    PcbTemp,
+}
+
+impl Sensor {
+    #[inline]
+    fn read(&self, ant: &message::ant::Payload) -> u32 {
+        match self {
+            Sensor::BatteryTemp => ant.battery_temp as u32,
+            Sensor::PcbTemp => ant.pcb_temp as u32,
+        }
+    }
+}
+
+/// Thermostat parameters. Temperatures are in raw telemetry units.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Whether the thermostat starts enabled. The ground can enable or disable it at any time with
+    /// `FEThermostatOn` / `FEThermostatOff`.
+    pub enabled: bool,
+
+    pub sensor: Sensor,
+
+    /// The heater is turned on below `setpoint - deadband` and off above `setpoint + deadband`.
+    pub setpoint: u32,
+    pub deadband: u32,
+
+    /// Shortest time between heater commands, to avoid cycling the heater rapidly.
+    #[serde(with = "crate::serde_millis")]
+    pub min_dwell: Duration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled:   false,
+            sensor:    Sensor::BatteryTemp,
+            setpoint:  0,
+            deadband:  0,
+            min_dwell: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Config {
+    pub fn validate(&self) -> Result<(), config::Error> {
+        if self.deadband > self.setpoint {
+            return Err(config::Error {
+                name:   "thermostat.deadband",
+                reason: "must not exceed setpoint",
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Report the parameters in effect.
+    pub fn log(&self) {
+        tracing::info!(
+            enabled = self.enabled,
+            sensor = ?self.sensor,
+            setpoint = self.setpoint,
+            deadband = self.deadband,
+            min_dwell = ?self.min_dwell,
+            "thermostat config"
+        );
+    }
+
+    /// Heater state called for by `temperature`, given that it was last commanded to `heating`.
+    /// `None` if it's within the deadband and should be left as it is.
+    pub fn demand(&self, temperature: u32, heating: Option<bool>) -> Option<bool> {
+        let want = if temperature < self.setpoint - self.deadband {
+            true
+        } else if temperature > self.setpoint.saturating_add(self.deadband) {
+            false
+        } else {
+            return None;
+        };
+
+        (heating != Some(want)).then_some(want)
+    }
+}
+
+#[inline]
+fn heater_event(on: bool) -> Event {
+    if on {
+        Event::AntHeaterOn
+    } else {
+        Event::AntHeaterOff
+    }
+}
+
+/// Turns the ant heater on and off to hold decoded ant telemetry temperatures around a setpoint.
+/// Off unless enabled in the config or from the ground. Heater commands are run as ant ops by the
+/// state machine, so they're only sent in states where the ground could send them too.
+pub struct Thermostat {
+    config:         Config,
+    subscribe_once: Once,
+    enabled:        bool,
+    /// What the heater was last commanded to, by us or by the ground, if anything.
+    heating:        Option<bool>,
+    /// Heater command of ours still awaiting its outcome.
+    requested:      Option<bool>,
+    last_command:   Option<Instant>,
+}
+
+impl Default for Thermostat {
+    fn default() -> Self {
+        Self::new(Config::default())
+    }
+}
+
+impl Thermostat {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            subscribe_once: Once::new(),
+            enabled: config.enabled,
+            heating: None,
+            requested: None,
+            last_command: None,
+        }
+    }
+
+    #[inline]
+    fn report(&self, report: Report) {
+        self.issue_system_async(ground::ThermostatReport(report));
+    }
+
+    fn set_enabled(&mut self, enabled: bool, ctx: &mut Context<Self>) {
+        if enabled == self.enabled {
+            return;
+        }
+
+        tracing::info!(enabled, "thermostat toggled from the ground");
+
+        self.enabled = enabled;
+        self.report(if enabled {
+            Report::Enabled
+        } else {
+            Report::Disabled
+        });
+
+        // don't leave the heater on with nothing watching it
+        if !enabled && self.heating != Some(false) {
+            self.command(false, None, ctx);
+        }
+    }
+
+    #[tracing::instrument(skip(self, ctx))]
+    fn command(&mut self, on: bool, temperature: Option<u32>, ctx: &mut Context<Self>) {
+        tracing::info!("commanding heater");
+
+        self.heating = Some(on);
+        self.requested = Some(on);
+        self.last_command = Some(Instant::now());
+        self.report(Report::Heater {
+            on,
+            temperature,
+        });
+
+        let fut = fut::wrap_future(params()).map(move |params, act: &mut Self, _ctx| {
+            let msg = message::command(&params, Destination::Ant, heater_event(on));
+            act.issue_system_async(RunOp(msg));
+        });
+
+        ctx.spawn(fut);
+    }
+
+    /// Track the heater through the outcome of a heater op, whether it was ours or the ground's.
+    fn op_report(&mut self, report: ant_op::Report) {
+        let (event, error) = match report {
+            ant_op::Report::Acked {
+                event,
+                ..
+            } => (event, None),
+            ant_op::Report::Rejected {
+                event,
+                state,
+            } => (event, Some(format!("not allowed in {state:?}"))),
+            ant_op::Report::Busy(event) => (event, Some("already in progress".to_owned())),
+            ant_op::Report::Failed {
+                event,
+                error,
+            } => (event, Some(error)),
+        };
+
+        let on = match event {
+            Event::AntHeaterOn => true,
+            Event::AntHeaterOff => false,
+            _ => return,
+        };
+
+        let Some(error) = error else {
+            tracing::debug!(on, "heater command acked");
+            self.heating = Some(on);
+
+            if self.requested == Some(on) {
+                self.requested = None;
+            }
+
+            return;
+        };
+
+        // unknown: try again once the dwell time is up
+        if self.heating == Some(on) {
+            self.heating = None;
+        }
+
+        if self.requested == Some(on) {
+            tracing::error!(%error, on, "heater command failed");
+
+            self.requested = None;
+            self.report(Report::Failed {
+                on,
+                error,
+            });
+        }
+    }
+}
+
+impl Actor for Thermostat {
+    type Context = Context<Self>;
+
+    #[tracing::instrument(skip_all)]
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_once.call_once(|| {
+            self.subscribe_async::<SystemBroker, serial::AntMessage>(ctx);
+            self.subscribe_async::<SystemBroker, ground::UpCommand>(ctx);
+            self.subscribe_async::<SystemBroker, ground::AntOpReport>(ctx);
+        });
+
+        tracing::info!(enabled = self.enabled, "thermostat started");
+    }
+}
+
+impl Supervised for Thermostat {}
+
+impl Handler<serial::AntMessage> for Thermostat {
+    type Result = ();
+
+    fn handle(&mut self, msg: serial::AntMessage, ctx: &mut Self::Context) -> Self::Result {
+        if !self.enabled {
+            return;
+        }
+
+        let temperature = self.config.sensor.read(&msg.0.payload);
+
+        let Some(on) = self.config.demand(temperature, self.heating) else {
+            return;
+        };
+
+        if let Some(last) = self.last_command {
+            if last.elapsed() < self.config.min_dwell {
+                tracing::debug!(temperature, on, "heater change deferred by dwell time");
+                return;
+            }
+        }
+
+        self.command(on, Some(temperature), ctx);
+    }
+}
+
+impl Handler<ground::UpCommand> for Thermostat {
+    type Result = ();
+
+    fn handle(&mut self, ground::UpCommand(msg): ground::UpCommand, ctx: &mut Self::Context) {
+        match msg.header.header.ty.event {
+            Event::FEThermostatOn => self.set_enabled(true, ctx),
+            Event::FEThermostatOff => self.set_enabled(false, ctx),
+            _ => {},
+        }
+    }
+}
+
+impl Handler<ground::AntOpReport> for Thermostat {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        ground::AntOpReport(report): ground::AntOpReport,
+        _ctx: &mut Self::Context,
+    ) {
+        self.op_report(report);
+    }
+}
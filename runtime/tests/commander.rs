@@ -0,0 +1,107 @@
+mod common;
+
+use std::time::Duration;
+
+use actix_broker::{
+    Broker,
+    SystemBroker,
+};
+
+use message::{
+    downlink::link::Report,
+    header::{
+        Destination,
+        Disposition,
+        Event,
+    },
+    source_info::Info,
+    BytesWrap,
+    SourceInfo,
+};
+use runtime::{
+    ground,
+    params,
+    serial,
+    system,
+};
+
+use common::{
+    collect,
+    next,
+    settle,
+};
+
+/// Sequence numbers handed out over a full cycle: held ones are skipped.
+async fn cycle() -> Vec<u8> {
+    let mut seqs = vec![];
+
+    for _ in 0..=u8::MAX {
+        seqs.push(system::seq().await);
+    }
+
+    seqs
+}
+
+#[actix::test]
+async fn timed_out_request_frees_its_seq() {
+    // the commander's sweep runs on tokio time: let it skip ahead whenever we're idle
+    tokio::time::pause();
+
+    let params = params().await;
+    let msg = message::command(&params, Destination::CentralStation, Event::CSPing);
+
+    // nothing acks on this side
+    let result = serial::send(msg, Some(Duration::from_millis(50))).await;
+    assert!(result.is_err());
+
+    assert!(!cycle().await.contains(&params.seq));
+
+    // the commander forgets the request on its next sweep after the deadline
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+
+    assert!(cycle().await.contains(&params.seq));
+}
+
+/// An ack of `msg` echoing `checksum`, as the central station would send it.
+fn ack(msg: &message::Message, checksum: u8) -> serial::AckMessage {
+    let mut ack = (**msg).clone();
+    ack.header.payload = SourceInfo::Info(Info {
+        header: ack.header.header,
+        checksum,
+    });
+
+    ack.header.header.ty.disposition = Disposition::Ack;
+    ack.payload = BytesWrap::default();
+
+    serial::AckMessage(message::Message::new(ack))
+}
+
+#[actix::test]
+async fn mismatched_ack_checksum_is_rejected() {
+    let mut reports = collect::<ground::LinkReport>();
+    settle().await;
+
+    let msg = message::command(&params().await, Destination::CentralStation, Event::CSPing);
+    let checksum = msg.checksum().unwrap()[0];
+
+    let request = actix::spawn(serial::send(msg.clone(), Some(Duration::from_secs(1))));
+    settle().await;
+
+    Broker::<SystemBroker>::issue_async(ack(&msg, !checksum));
+
+    assert_eq!(next(&mut reports).await.0, Report::AckChecksumMismatch {
+        event:      Event::CSPing,
+        expected:   checksum,
+        actual:     !checksum,
+        mismatches: 1,
+    });
+
+    // the request is still waiting for its own ack
+    assert!(!request.is_finished());
+    assert!(!cycle().await.contains(&msg.header.header.seq));
+
+    Broker::<SystemBroker>::issue_async(ack(&msg, checksum));
+
+    let response = request.await.unwrap().unwrap();
+    assert!(matches!(response, serial::Response::Message(_)));
+}
@@ -0,0 +1,75 @@
+//! Helpers for driving actors over the system broker.
+
+#![allow(dead_code)]
+
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_broker::{
+    BrokerIssue,
+    BrokerSubscribe,
+    SystemBroker,
+};
+use tokio::sync::mpsc;
+
+use message::header::{
+    Destination,
+    Event,
+};
+use runtime::{
+    ground,
+    params,
+};
+
+/// Forwards every `M` issued on the system broker to a channel.
+struct Collect<M>(mpsc::UnboundedSender<M>);
+
+impl<M> Actor for Collect<M>
+where
+    M: actix::Message<Result = ()> + Clone + Send + Unpin + 'static,
+{
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.subscribe_async::<SystemBroker, M>(ctx);
+    }
+}
+
+impl<M> Handler<M> for Collect<M>
+where
+    M: actix::Message<Result = ()> + Clone + Send + Unpin + 'static,
+{
+    type Result = ();
+
+    fn handle(&mut self, msg: M, _ctx: &mut Self::Context) {
+        let _ = self.0.send(msg);
+    }
+}
+
+/// Every `M` issued on the system broker from now on.
+pub fn collect<M>() -> mpsc::UnboundedReceiver<M>
+where
+    M: actix::Message<Result = ()> + Clone + Send + Unpin + 'static,
+{
+    let (tx, rx) = mpsc::unbounded_channel();
+    Collect(tx).start();
+
+    rx
+}
+
+/// Give newly started actors a chance to subscribe.
+pub async fn settle() {
+    tokio::time::sleep(Duration::from_millis(20)).await;
+}
+
+/// The next message on `rx`, failing if none arrives within a second.
+pub async fn next<M>(rx: &mut mpsc::UnboundedReceiver<M>) -> M {
+    tokio::time::timeout(Duration::from_secs(1), rx.recv()).await.unwrap().unwrap()
+}
+
+/// Issue a ground command for the frontend.
+pub async fn uplink(event: Event, payload: impl AsRef<[u8]>) {
+    let msg = message::command_with_payload(&params().await, Destination::Frontend, event, payload);
+
+    Broker::<SystemBroker>::issue_async(ground::UpCommand(msg));
+}
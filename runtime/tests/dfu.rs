@@ -0,0 +1,121 @@
+use message::{
+    dfu::{
+        checksum,
+        ImageInfo,
+    },
+    downlink::dfu::Report,
+};
+use runtime::dfu::{
+    Config,
+    Error,
+    Image,
+    Staging,
+};
+
+fn info(image: &[u8]) -> ImageInfo {
+    ImageInfo {
+        size:     image.len() as u32,
+        checksum: checksum(image),
+    }
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn reassembles_out_of_order() {
+    let data = (0..=255u8).cycle().take(1000).collect::<Vec<_>>();
+    let mut image = Image::new(info(&data), 4096).unwrap();
+
+    let chunks = data.chunks(64).enumerate().collect::<Vec<_>>();
+
+    for &(i, chunk) in chunks.iter().rev() {
+        assert!(!image.is_complete());
+        image.insert((i * 64) as u32, chunk).unwrap();
+    }
+
+    // duplicates and overlaps don't count twice
+    image.insert(0, &data[..100]).unwrap();
+    image.insert(32, &data[32..96]).unwrap();
+
+    assert_eq!(image.received(), 1000);
+    image.verify().unwrap();
+    assert_eq!(image.data(), &data[..]);
+}
+
+#[test]
+fn reports_missing_bytes() {
+    let data = vec![0xa5; 100];
+    let mut image = Image::new(info(&data), 4096).unwrap();
+
+    image.insert(0, &data[..40]).unwrap();
+    image.insert(60, &data[60..]).unwrap();
+
+    assert_eq!(image.received(), 80);
+    assert_eq!(
+        image.verify(),
+        Err(Error::Incomplete {
+            received: 80,
+            size:     100,
+        })
+    );
+}
+
+#[test]
+fn rejects_bad_chunks() {
+    let data = vec![0; 100];
+    let mut image = Image::new(info(&data), 4096).unwrap();
+
+    assert!(matches!(image.insert(90, &[0; 20]), Err(Error::OutOfBounds { .. })));
+    assert!(matches!(image.insert(u32::MAX, &[0; 2]), Err(Error::OutOfBounds { .. })));
+
+    assert!(matches!(Image::new(info(&data), 50), Err(Error::TooLarge { .. })));
+    assert!(matches!(Image::new(info(&[]), 50), Err(Error::Empty)));
+}
+
+#[test]
+fn rejects_checksum_mismatch() {
+    let data = vec![1, 2, 3, 4];
+    let mut image = Image::new(info(&data), 4096).unwrap();
+
+    image.insert(0, &[1, 2, 3, 5]).unwrap();
+
+    assert!(matches!(image.verify(), Err(Error::ChecksumMismatch { .. })));
+}
+
+#[test]
+fn staging_reports() {
+    let data = vec![7; 100];
+    let mut staging = Staging::default();
+
+    assert!(matches!(staging.chunk(0, &data), Some(Report::Rejected(_))));
+    assert!(staging.verified().is_err());
+
+    assert_eq!(staging.begin(info(&data), 4096), Report::Receiving {
+        received: 0,
+        size:     100,
+    });
+
+    assert!(matches!(
+        staging.chunk(0, &data[..50]),
+        Some(Report::Receiving {
+            received: 50,
+            ..
+        })
+    ));
+    assert!(staging.verified().is_err());
+
+    assert_eq!(
+        staging.chunk(50, &data[50..]),
+        Some(Report::Verified {
+            size:     100,
+            checksum: checksum(&data),
+        })
+    );
+    assert_eq!(staging.verified().unwrap().data(), &data[..]);
+
+    staging.clear();
+    assert!(staging.verified().is_err());
+}
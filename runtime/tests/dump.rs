@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use message::{
+    header::{
+        Destination,
+        Event,
+    },
+    Header,
+    MissionEpoch,
+    Params,
+};
+use runtime::dump::{
+    Config,
+    Dump,
+};
+
+fn dump() -> Dump {
+    let params = Params {
+        time: at(1000),
+        seq:  7,
+    };
+
+    Dump::new(Header::command(&params, Destination::Ant, Event::AntStart).unique_id())
+}
+
+#[inline]
+fn at(ms: u32) -> MissionEpoch {
+    MissionEpoch::from(ms)
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn in_order_dump() {
+    let mut dump = dump();
+
+    for i in 0..10u8 {
+        assert!(dump.insert(at(2000 + i as u32 * 100), 200u8.wrapping_add(i), 500 - i as u16 * 10));
+    }
+
+    let stats = dump.stats();
+
+    assert_eq!(stats.packets, 10);
+    assert_eq!(stats.missing, 0);
+    assert_eq!(stats.duplicates, 0);
+    assert_eq!(stats.out_of_order, 0);
+    assert_eq!(stats.duration, Duration::from_millis(900));
+    assert_eq!(stats.fram_drained, 90);
+}
+
+#[test]
+fn gaps_and_duplicates() {
+    let mut dump = dump();
+
+    assert!(dump.insert(at(100), 1, 0));
+    assert!(dump.insert(at(200), 2, 0));
+    assert!(!dump.insert(at(200), 2, 0));
+
+    // 3 and 4 skipped
+    assert!(dump.insert(at(500), 5, 0));
+    assert_eq!(dump.stats().missing, 2);
+
+    // 3 turns up late
+    assert!(dump.insert(at(300), 3, 0));
+
+    let stats = dump.stats();
+
+    assert_eq!(stats.packets, 4);
+    assert_eq!(stats.duplicates, 1);
+    assert_eq!(stats.missing, 1);
+    assert_eq!(stats.out_of_order, 1);
+    assert_eq!(stats.duration, Duration::from_millis(400));
+}
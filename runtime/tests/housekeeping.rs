@@ -0,0 +1,73 @@
+mod common;
+
+use std::time::Duration;
+
+use actix::prelude::*;
+use tokio::sync::mpsc;
+
+use message::payload::realtime_status::Flags;
+use runtime::{
+    ground,
+    housekeeping::{
+        raise,
+        Config,
+        Housekeeping,
+    },
+};
+
+use common::{
+    collect,
+    next,
+    settle,
+};
+
+async fn flags(rx: &mut mpsc::UnboundedReceiver<ground::StatusReport>) -> Flags {
+    next(rx).await.0.flags
+}
+
+/// Whether no frame arrives for a while.
+async fn quiet(rx: &mut mpsc::UnboundedReceiver<ground::StatusReport>) -> bool {
+    tokio::time::timeout(Duration::from_millis(50), rx.recv()).await.is_err()
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn rejects_zero_interval() {
+    let config = Config {
+        interval: Duration::ZERO,
+    };
+
+    assert!(config.validate().is_err());
+}
+
+#[actix::test]
+async fn new_flags_report_immediately() {
+    let mut frames = collect();
+    settle().await;
+
+    Housekeeping::new(Config {
+        interval: Duration::from_secs(60),
+    })
+    .start();
+
+    assert_eq!(flags(&mut frames).await, Flags::BOOTED);
+
+    raise(Flags::SERIAL_ERROR);
+    assert_eq!(flags(&mut frames).await, Flags::SERIAL_ERROR);
+
+    // already in the last frame: held for the next one
+    raise(Flags::SERIAL_ERROR);
+    assert!(quiet(&mut frames).await);
+
+    raise(Flags::SOCKET_ERROR);
+    assert_eq!(flags(&mut frames).await, Flags::SERIAL_ERROR | Flags::SOCKET_ERROR);
+
+    // pending, or in the last frame
+    raise(Flags::SERIAL_ERROR);
+    raise(Flags::SOCKET_ERROR);
+    assert!(quiet(&mut frames).await);
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use actix::prelude::*;
+use actix_broker::{
+    Broker,
+    SystemBroker,
+};
+use packed_struct::PackedStruct;
+
+use message::{
+    downlink::limits::{
+        Field,
+        Level,
+        Report,
+    },
+    header::{
+        Destination,
+        Event,
+    },
+};
+use runtime::{
+    ground,
+    limits::{
+        Band,
+        Config,
+        Limit,
+        Limits,
+        Response,
+    },
+    serial,
+    table::Command,
+    RunOp,
+};
+
+use common::{
+    collect,
+    next,
+    settle,
+};
+
+fn battery_temp() -> Limit {
+    Limit {
+        field:      Field::BatteryTemp,
+        yellow:     Band {
+            low:  Some(100),
+            high: Some(200),
+        },
+        red:        Band {
+            low:  Some(50),
+            high: Some(250),
+        },
+        hysteresis: 10,
+        on_nominal: vec![],
+        on_yellow:  vec![],
+        on_red:     vec![Response::StopAnt],
+    }
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn levels_rise_immediately() {
+    let limit = battery_temp();
+
+    assert_eq!(limit.next_level(Level::Nominal, 150), Level::Nominal);
+    assert_eq!(limit.next_level(Level::Nominal, 201), Level::Yellow);
+    assert_eq!(limit.next_level(Level::Nominal, 99), Level::Yellow);
+    assert_eq!(limit.next_level(Level::Nominal, 251), Level::Red);
+    assert_eq!(limit.next_level(Level::Yellow, 49), Level::Red);
+}
+
+#[test]
+fn levels_fall_with_hysteresis() {
+    let limit = battery_temp();
+
+    // back inside the red band, but not by enough
+    assert_eq!(limit.next_level(Level::Red, 245), Level::Red);
+    assert_eq!(limit.next_level(Level::Red, 240), Level::Yellow);
+
+    // clear of both bands at once
+    assert_eq!(limit.next_level(Level::Red, 150), Level::Nominal);
+
+    assert_eq!(limit.next_level(Level::Yellow, 195), Level::Yellow);
+    assert_eq!(limit.next_level(Level::Yellow, 190), Level::Nominal);
+    assert_eq!(limit.next_level(Level::Yellow, 105), Level::Yellow);
+    assert_eq!(limit.next_level(Level::Yellow, 110), Level::Nominal);
+}
+
+#[test]
+fn rejects_bad_limits() {
+    let config = |limits| Config {
+        limits,
+        ..Config::default()
+    };
+
+    config(vec![battery_temp()]).validate().unwrap();
+    assert!(config(vec![battery_temp(), battery_temp()]).validate().is_err());
+
+    let mut inverted = battery_temp();
+    inverted.yellow.low = Some(300);
+    assert!(config(vec![inverted]).validate().is_err());
+
+    let mut outside = battery_temp();
+    outside.yellow.high = Some(300);
+    assert!(config(vec![outside]).validate().is_err());
+
+    let mut frontend = battery_temp();
+    frontend.on_yellow = vec![Response::Send {
+        command: Command {
+            destination: Destination::Frontend,
+            event:       Event::FEPing,
+        },
+    }];
+    assert!(config(vec![frontend]).validate().is_err());
+
+    let mut heater = battery_temp();
+    heater.on_yellow = vec![Response::Send {
+        command: Command::ant(Event::AntHeaterOn),
+    }];
+    config(vec![heater]).validate().unwrap();
+}
+
+fn telemetry(battery_temp: u16) -> serial::Telemetry {
+    serial::Telemetry {
+        cs:  message::cs::Payload::unpack(&[0; 20]).unwrap(),
+        ant: message::ant::Payload {
+            battery_temp,
+            ..message::ant::Payload::unpack(&[0; 52]).unwrap()
+        },
+    }
+}
+
+#[actix::test]
+async fn ant_op_responses_go_to_state_machine() {
+    let mut reports = collect::<ground::LimitsReport>();
+    let mut ops = collect::<RunOp>();
+    settle().await;
+
+    let mut limit = battery_temp();
+    limit.on_yellow = vec![Response::Send {
+        command: Command::ant(Event::AntHeaterOn),
+    }];
+
+    Limits::new(
+        Config {
+            limits: vec![limit],
+            ..Config::default()
+        },
+        [Event::AntHeaterOn],
+    )
+    .start();
+    settle().await;
+
+    Broker::<SystemBroker>::issue_async(telemetry(90));
+
+    assert_eq!(next(&mut reports).await.0, Report::Changed {
+        field: Field::BatteryTemp,
+        from:  Level::Nominal,
+        to:    Level::Yellow,
+        value: 90,
+    });
+
+    let RunOp(msg) = next(&mut ops).await;
+    assert_eq!(msg.header.header.ty.event, Event::AntHeaterOn);
+    assert_eq!(msg.header.header.destination, Destination::Ant);
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use std::time::Duration;
+
+use actix::prelude::*;
+use bytes::Bytes;
+use tokio::sync::mpsc;
+
+use message::{
+    downlink::schedule::Report,
+    header::{
+        Destination,
+        Event,
+    },
+    schedule::{
+        Command,
+        When,
+    },
+    MissionEpoch,
+};
+use runtime::{
+    ground,
+    scheduler::{
+        resolve,
+        Config,
+        Scheduler,
+    },
+    RunOp,
+};
+
+use common::{
+    collect,
+    next,
+    settle,
+};
+
+async fn uplink(cmd: Command) {
+    common::uplink(Event::FESchedule, cmd.pack_to_vec().unwrap()).await;
+}
+
+fn add(when: When, event: Event) -> Command {
+    Command::Add {
+        when,
+        destination: Destination::Ant,
+        event,
+        payload: Bytes::from_static(&[1, 2, 3]),
+    }
+}
+
+/// Start `scheduler` with a collector for its reports, leaving time for both to subscribe.
+async fn start(scheduler: Scheduler) -> mpsc::UnboundedReceiver<ground::ScheduleReport> {
+    let reports = collect();
+    scheduler.start();
+
+    settle().await;
+
+    reports
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn resolves_times() {
+    let now = MissionEpoch::from(1_000);
+    let at = MissionEpoch::from(500);
+
+    assert_eq!(resolve(When::At(at), now), Some(at));
+    assert_eq!(resolve(When::After(Duration::from_secs(5)), now), Some(MissionEpoch::from(6_000)));
+    assert_eq!(resolve(When::After(Duration::ZERO), now), Some(now));
+
+    // the mission epoch wraps
+    let late = MissionEpoch::from(u32::MAX - 9);
+    assert_eq!(
+        resolve(When::After(Duration::from_millis(100)), late),
+        Some(MissionEpoch::from(90))
+    );
+
+    // too far ahead to tell from the past
+    assert_eq!(resolve(When::After(Duration::from_millis(1 << 31)), now), None);
+    assert_eq!(resolve(When::After(Duration::from_secs(u64::MAX)), now), None);
+}
+
+#[actix::test]
+async fn add_list_cancel_clear() {
+    let mut reports = start(Scheduler::new(Config::default(), [])).await;
+
+    let now = u32::from(MissionEpoch::now());
+    let hour = Duration::from_secs(3600);
+
+    uplink(add(When::After(hour), Event::AntStart)).await;
+    let ground::ScheduleReport(Report::Scheduled(first)) = next(&mut reports).await else {
+        panic!("expected first command to be scheduled");
+    };
+
+    let lead = u32::from(first.at).wrapping_sub(now);
+    assert!((3_600_000..3_601_000).contains(&lead), "scheduled {lead}ms out");
+    assert_eq!((first.destination, first.event), (Destination::Ant, Event::AntStart));
+
+    let at = MissionEpoch::from(now.wrapping_add(7_200_000));
+    uplink(add(When::At(at), Event::AntStop)).await;
+    let ground::ScheduleReport(Report::Scheduled(second)) = next(&mut reports).await else {
+        panic!("expected second command to be scheduled");
+    };
+
+    assert_eq!(second.at, at);
+    assert_ne!(second.id, first.id);
+
+    uplink(add(When::At(MissionEpoch::from(now.wrapping_sub(1_000))), Event::AntStop)).await;
+    assert!(matches!(next(&mut reports).await.0, Report::Rejected(_)));
+
+    uplink(Command::List).await;
+    assert_eq!(next(&mut reports).await.0, Report::Pending(vec![first, second]));
+
+    uplink(Command::Cancel {
+        id: first.id,
+    })
+    .await;
+    assert_eq!(next(&mut reports).await.0, Report::Cancelled(first));
+
+    uplink(Command::Cancel {
+        id: first.id,
+    })
+    .await;
+    assert!(matches!(next(&mut reports).await.0, Report::Rejected(_)));
+
+    uplink(Command::List).await;
+    assert_eq!(next(&mut reports).await.0, Report::Pending(vec![second]));
+
+    uplink(Command::Clear).await;
+    assert_eq!(next(&mut reports).await.0, Report::Cleared {
+        count: 1,
+    });
+
+    uplink(Command::List).await;
+    assert_eq!(next(&mut reports).await.0, Report::Pending(vec![]));
+}
+
+#[actix::test]
+async fn due_ant_op_goes_to_state_machine() {
+    let mut ops = collect::<RunOp>();
+    let mut reports = start(Scheduler::new(Config::default(), [Event::AntMoveForward])).await;
+
+    uplink(add(When::After(Duration::from_millis(50)), Event::AntMoveForward)).await;
+    let ground::ScheduleReport(Report::Scheduled(entry)) = next(&mut reports).await else {
+        panic!("expected op to be scheduled");
+    };
+
+    assert_eq!(next(&mut reports).await.0, Report::Dispatched(entry));
+
+    let RunOp(msg) = next(&mut ops).await;
+    assert_eq!(msg.header.header.ty.event, Event::AntMoveForward);
+    assert_eq!(msg.payload.as_ref(), &[1, 2, 3]);
+}
@@ -0,0 +1,240 @@
+mod common;
+
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_broker::{
+    Broker,
+    SystemBroker,
+};
+use tokio::sync::mpsc;
+
+use message::{
+    downlink::sequence::{
+        Report,
+        Summary,
+    },
+    header::{
+        Destination,
+        Event,
+    },
+    sequence::{
+        Command,
+        Step,
+        Wait,
+    },
+};
+use runtime::{
+    ground,
+    params,
+    sequencer::{
+        check_step,
+        Config,
+        Sequencer,
+    },
+    serial,
+};
+
+use common::{
+    collect,
+    next,
+    settle,
+};
+
+fn step(command: Option<(Destination, Event)>, wait: Wait, timeout: Duration) -> Step {
+    Step {
+        command,
+        wait,
+        timeout,
+        retries: 0,
+    }
+}
+
+fn pause(ms: u64) -> Step {
+    step(None, Wait::Nothing, Duration::from_millis(ms))
+}
+
+fn wait_for(event: Event, ms: u64) -> Step {
+    step(None, Wait::Event(event), Duration::from_millis(ms))
+}
+
+async fn uplink(cmd: Command) {
+    common::uplink(Event::FESequence, cmd.pack_to_vec().unwrap()).await;
+}
+
+async fn define(name: &str, steps: Vec<Step>) {
+    uplink(Command::Define {
+        name: name.to_owned(),
+        steps,
+    })
+    .await;
+}
+
+async fn run(name: &str) {
+    uplink(Command::Run {
+        name: name.to_owned(),
+    })
+    .await;
+}
+
+/// Report `event` as having arrived from the central station.
+async fn arrive(event: Event) {
+    let msg = message::command(&params().await, Destination::Frontend, event);
+    Broker::<SystemBroker>::issue_async(serial::DownMessage(msg));
+}
+
+/// Start `sequencer` with a collector for its reports, leaving time for both to subscribe.
+async fn start(sequencer: Sequencer) -> mpsc::UnboundedReceiver<ground::SequenceReport> {
+    let reports = collect();
+    Actor::start(sequencer);
+
+    settle().await;
+
+    reports
+}
+
+async fn report(rx: &mut mpsc::UnboundedReceiver<ground::SequenceReport>) -> Report {
+    next(rx).await.0
+}
+
+fn done(name: &str, index: usize, step: Step) -> Report {
+    Report::StepDone {
+        name: name.to_owned(),
+        index,
+        step,
+    }
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn step_checks() {
+    let ant = Some((Destination::Ant, Event::AntStart));
+    let second = Duration::from_secs(1);
+
+    check_step(&step(ant, Wait::Nothing, Duration::ZERO)).unwrap();
+    check_step(&step(ant, Wait::Ack, second)).unwrap();
+    check_step(&step(None, Wait::Event(Event::CSBLEConnect), second)).unwrap();
+    check_step(&step(None, Wait::Nothing, second)).unwrap();
+
+    assert!(check_step(&step(None, Wait::Ack, second)).is_err());
+    assert!(check_step(&step(ant, Wait::Ack, Duration::ZERO)).is_err());
+    assert!(check_step(&step(None, Wait::Nothing, Duration::ZERO)).is_err());
+
+    let frontend = Some((Destination::Frontend, Event::FEPing));
+    assert!(check_step(&step(frontend, Wait::Nothing, Duration::ZERO)).is_err());
+}
+
+#[actix::test]
+async fn runs_steps_in_order() {
+    let mut reports = start(Sequencer::default()).await;
+    let steps = vec![pause(10), wait_for(Event::CSBLEConnect, 1_000), pause(10)];
+
+    define("seq", steps.clone()).await;
+    assert_eq!(
+        report(&mut reports).await,
+        Report::Defined(Summary {
+            name:  "seq".to_owned(),
+            steps: 3,
+        })
+    );
+
+    run("seq").await;
+    assert!(matches!(report(&mut reports).await, Report::Started(_)));
+    assert_eq!(report(&mut reports).await, done("seq", 0, steps[0]));
+
+    // only the awaited event moves the sequence on
+    arrive(Event::CSBLEDisconnect).await;
+    assert!(tokio::time::timeout(Duration::from_millis(50), reports.recv()).await.is_err());
+
+    arrive(Event::CSBLEConnect).await;
+    assert_eq!(report(&mut reports).await, done("seq", 1, steps[1]));
+    assert_eq!(report(&mut reports).await, done("seq", 2, steps[2]));
+    assert_eq!(report(&mut reports).await, Report::Completed("seq".to_owned()));
+}
+
+#[actix::test]
+async fn aborts() {
+    let mut reports = start(Sequencer::default()).await;
+
+    define("long", vec![pause(10_000)]).await;
+    define("short", vec![wait_for(Event::CSBLEConnect, 30)]).await;
+    report(&mut reports).await;
+    report(&mut reports).await;
+
+    run("long").await;
+    assert!(matches!(report(&mut reports).await, Report::Started(_)));
+
+    // one at a time
+    run("short").await;
+    assert!(matches!(report(&mut reports).await, Report::Rejected(_)));
+
+    uplink(Command::Abort).await;
+    assert_eq!(report(&mut reports).await, Report::Aborted {
+        name:   "long".to_owned(),
+        index:  0,
+        reason: "aborted from the ground".to_owned(),
+    });
+
+    uplink(Command::Abort).await;
+    assert!(matches!(report(&mut reports).await, Report::Rejected(_)));
+
+    // a step whose event never arrives aborts the sequence
+    run("short").await;
+    assert!(matches!(report(&mut reports).await, Report::Started(_)));
+    assert!(matches!(
+        report(&mut reports).await,
+        Report::Aborted { index: 0, reason, .. } if reason.contains("timed out")
+    ));
+}
+
+#[test]
+fn stored_sequences_survive_restart() {
+    let path = std::env::temp_dir().join(format!("antrelay-sequences-{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let steps = vec![pause(10), wait_for(Event::CSBLEConnect, 1_000)];
+
+    System::new().block_on({
+        let path = path.clone();
+
+        async move {
+            let mut reports = start(Sequencer::new(Some(path), Config::default())).await;
+
+            define("kept", steps).await;
+            define("dropped", vec![pause(10)]).await;
+            uplink(Command::Delete {
+                name: "dropped".to_owned(),
+            })
+            .await;
+
+            assert!(matches!(report(&mut reports).await, Report::Defined(_)));
+            assert!(matches!(report(&mut reports).await, Report::Defined(_)));
+            assert!(matches!(report(&mut reports).await, Report::Deleted(_)));
+        }
+    });
+
+    let stored = System::new().block_on({
+        let path = path.clone();
+
+        async move {
+            let mut reports = start(Sequencer::new(Some(path), Config::default())).await;
+
+            uplink(Command::List).await;
+            report(&mut reports).await
+        }
+    });
+
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        stored,
+        Report::Stored(vec![Summary {
+            name:  "kept".to_owned(),
+            steps: 2,
+        }])
+    );
+}
@@ -0,0 +1,135 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use bytes::Bytes;
+use message::{
+    downlink::queue::Priority,
+    header::Event,
+    Header,
+    Params,
+};
+use runtime::serial::queue::{
+    classify,
+    Bucket,
+    Class,
+    Config,
+    Overflow,
+    Push,
+    Queued,
+    Queues,
+};
+
+fn item(seq: u8, event: Event, now: Instant) -> Queued {
+    let header = Header::command(
+        &Params {
+            time: 0u32.into(),
+            seq,
+        },
+        message::header::Destination::Ant,
+        event,
+    );
+
+    Queued {
+        id: header.unique_id(),
+        event,
+        packet: Bytes::from_static(&[0; 16]),
+        queued: now,
+    }
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn budget_follows_baud() {
+    let config = Config {
+        budget_percent: 50,
+        ..Default::default()
+    };
+
+    assert_eq!(config.budget(115200), 5760);
+}
+
+#[test]
+fn classifies_commands() {
+    assert_eq!(classify(Event::AntStop), Priority::Safety);
+    assert_eq!(classify(Event::CSAntDFUPacket), Priority::Bulk);
+    assert_eq!(classify(Event::AntStart), Priority::Control);
+}
+
+#[test]
+fn serves_in_priority_order() {
+    let now = Instant::now();
+    let mut queues = Queues::new(Config::default());
+
+    for seq in 0..4 {
+        assert_eq!(
+            queues.push(Priority::Bulk, item(seq, Event::CSAntDFUPacket, now)),
+            Push::Queued
+        );
+    }
+
+    assert_eq!(queues.push(Priority::Control, item(10, Event::AntStart, now)), Push::Queued);
+    assert_eq!(queues.push(Priority::Safety, item(20, Event::AntStop, now)), Push::Queued);
+
+    let order = std::iter::from_fn(|| queues.pop(now)).map(|q| q.event).collect::<Vec<_>>();
+
+    assert_eq!(order[..3], [Event::AntStop, Event::AntStart, Event::CSAntDFUPacket]);
+    assert_eq!(order.len(), 6);
+}
+
+#[test]
+fn overflow_semantics() {
+    let now = Instant::now();
+    let mut queues = Queues::new(Config {
+        safety: Class {
+            capacity: 1,
+            overflow: Overflow::DropOldest,
+        },
+        bulk: Class {
+            capacity: 1,
+            overflow: Overflow::Reject,
+        },
+        ..Default::default()
+    });
+
+    queues.push(Priority::Safety, item(0, Event::AntStop, now));
+    assert!(
+        matches!(queues.push(Priority::Safety, item(1, Event::AntStop, now)), Push::Dropped(q) if q.id == item(0, Event::AntStop, now).id)
+    );
+
+    queues.push(Priority::Bulk, item(2, Event::CSAntDFUPacket, now));
+    assert!(
+        matches!(queues.push(Priority::Bulk, item(3, Event::CSAntDFUPacket, now)), Push::Rejected(q) if q.id == item(3, Event::CSAntDFUPacket, now).id)
+    );
+
+    let stats = queues.take_stats();
+
+    assert_eq!(stats[0].dropped, 1);
+    assert_eq!(stats[2].rejected, 1);
+    assert_eq!(queues.depth(Priority::Safety), 1);
+    assert_eq!(queues.depth(Priority::Bulk), 1);
+}
+
+#[test]
+fn bucket_paces_writes() {
+    let start = Instant::now();
+    let mut bucket = Bucket::new(100, Duration::from_secs(1), start);
+
+    // a full bucket allows a burst
+    assert_eq!(bucket.delay(100, start), Duration::ZERO);
+    bucket.take(100);
+
+    assert_eq!(bucket.delay(50, start), Duration::from_millis(500));
+    assert_eq!(bucket.delay(50, start + Duration::from_millis(500)), Duration::ZERO);
+
+    // larger than the bucket: waits for it to fill, then goes into debt
+    let later = start + Duration::from_secs(10);
+    assert_eq!(bucket.delay(1000, later), Duration::ZERO);
+    bucket.take(1000);
+    assert!(bucket.delay(1, later) > Duration::from_secs(9));
+}
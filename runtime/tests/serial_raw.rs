@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use runtime::serial::raw::{
+    Config,
+    Identity,
+};
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn rejects_bad_config() {
+    let base_over_max = Config {
+        base: Duration::from_secs(60),
+        ..Default::default()
+    };
+
+    let zero_poll = Config {
+        poll_interval: Duration::ZERO,
+        ..Default::default()
+    };
+
+    assert!(base_over_max.validate().is_err());
+    assert!(zero_poll.validate().is_err());
+}
+
+#[test]
+fn backoff_grows_and_caps() {
+    let config = Config {
+        base:          Duration::from_millis(100),
+        max_delay:     Duration::from_secs(5),
+        poll_interval: Duration::from_millis(10),
+    };
+
+    for _ in 0..100 {
+        let first = config.backoff(1);
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(150));
+
+        let fourth = config.backoff(4);
+        assert!(fourth >= Duration::from_millis(400) && fourth <= Duration::from_millis(1200));
+
+        for failures in [10, 31, 32, 1000, u32::MAX] {
+            let delay = config.backoff(failures);
+            assert!(delay >= Duration::from_millis(2500) && delay <= config.max_delay);
+        }
+    }
+}
+
+#[test]
+fn identity_tracks_replacement() {
+    let path = std::env::temp_dir().join(format!("antrelay-serial-{}", std::process::id()));
+
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(Identity::of(&path), None);
+
+    std::fs::write(&path, b"").unwrap();
+    let first = Identity::of(&path).unwrap();
+    assert_eq!(Identity::of(&path), Some(first));
+
+    // keep the original alive so the replacement can't reuse its inode
+    let held = path.with_extension("old");
+    std::fs::rename(&path, &held).unwrap();
+    assert_eq!(Identity::of(&path), None);
+
+    std::fs::write(&path, b"").unwrap();
+    let second = Identity::of(&path).unwrap();
+
+    std::fs::remove_file(&path).unwrap();
+    std::fs::remove_file(&held).unwrap();
+
+    if cfg!(unix) {
+        assert_ne!(first, second);
+    }
+}
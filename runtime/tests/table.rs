@@ -0,0 +1,280 @@
+use std::time::Duration;
+
+use message::{
+    downlink::state_machine::Alarm,
+    header::Event,
+};
+use runtime::{
+    table::{
+        Action,
+        Error,
+        Retry,
+        Table,
+        Transition,
+    },
+    timing::{
+        Delay,
+        Timing,
+    },
+    State,
+};
+
+#[test]
+fn default_table_is_valid() {
+    Table::default().validate().unwrap();
+}
+
+#[test]
+fn default_table_roundtrips_json() {
+    let table = Table::default();
+
+    let encoded = serde_json::to_string(&table).unwrap();
+    let decoded: Table = serde_json::from_str(&encoded).unwrap();
+
+    assert_eq!(table, decoded);
+}
+
+#[test]
+fn lookup_precedence() {
+    let table = Table::default();
+
+    let t = table.lookup(State::AntReady, None, Event::FERoverStop).unwrap();
+    assert_eq!(t.to, Some(State::AntRun));
+
+    let t = table.lookup(State::GarageOpen, None, Event::FERoverStop).unwrap();
+    assert_eq!(t.from, None);
+    assert_eq!(t.to, None);
+
+    assert!(table.lookup(State::FlightIdle, None, Event::CSRelay).is_none());
+}
+
+#[test]
+fn rejects_ambiguous() {
+    let mut table = Table::default();
+    let dup = table.transitions[0].clone();
+    table.transitions.push(dup);
+
+    assert!(matches!(table.validate(), Err(Error::Ambiguous { .. })));
+}
+
+#[test]
+fn rejects_unreachable() {
+    let mut table = Table::default();
+    table.transitions.retain(|t| t.to != Some(State::AntRun));
+    table.resume.clear();
+
+    assert!(matches!(table.validate(), Err(Error::Unreachable(State::AntRun))));
+}
+
+#[test]
+fn rejects_conflicting_transitions() {
+    let mut table = Table::default();
+
+    let later = |to| Action::Later {
+        delay:   Delay::Millis(1000),
+        to:      Some(to),
+        actions: vec![],
+    };
+
+    table.transitions.push(Transition {
+        from:    Some(State::AntRun),
+        event:   Event::AntPing,
+        guard:   None,
+        to:      None,
+        actions: vec![later(State::AntReady), later(State::GarageOpen)],
+        report:  false,
+    });
+
+    assert!(matches!(table.validate(), Err(Error::ConflictingTransitions(Event::AntPing))));
+}
+
+#[test]
+fn cs_power_cycle_recovery() {
+    let table = Table::default();
+
+    let reopens_garage = |t: &Transition| {
+        t.actions.iter().any(|a| {
+            matches!(a, Action::SendRetry { command, .. } if command.event == Event::CSGarageOpen)
+        })
+    };
+
+    for state in
+        [State::GarageOpen, State::BLEConnected, State::AntReady, State::AntRun, State::AntStopping]
+    {
+        let t = table.lookup(state, None, Event::FECSPowerCycled).unwrap();
+
+        assert_eq!(t.to, Some(State::PingCentralStation));
+        assert!(t.report);
+        assert!(reopens_garage(t));
+        assert!(!t.actions.contains(&Action::ClearPending));
+    }
+
+    let t = table.lookup(State::PingCentralStation, None, Event::FECSPowerCycled).unwrap();
+    assert!(!reopens_garage(t));
+
+    let t = table.lookup(State::FlightIdle, None, Event::FECSPowerCycled).unwrap();
+    assert_eq!(t.to, None);
+    assert!(t.actions.contains(&Action::Retain));
+}
+
+#[test]
+fn uplink_loss_safes_ant() {
+    let table = Table::default();
+
+    let t = table.lookup(State::AntRun, None, Event::FEUplinkLost).unwrap();
+    assert_eq!(t.to, Some(State::AntStopping));
+    assert!(t.actions.iter().any(|a| matches!(
+        a,
+        Action::SendRetry { command, on_success: Some(State::AntReady), .. }
+            if command.event == Event::AntStop
+    )));
+    assert!(t.actions.contains(&Action::ClearPending));
+
+    // a queued rover stop is stale once the rover is back: it has to ask again
+    for state in [State::AntReady, State::AntStopping] {
+        let t = table.lookup(state, Some(Event::FERoverStop), Event::FEUplinkResumed).unwrap();
+        assert_eq!(t.to, None);
+        assert!(t.actions.contains(&Action::ClearPending));
+        assert!(!t.actions.contains(&Action::ReplayPending));
+        assert!(t.actions.contains(&Action::Retain));
+    }
+
+    for state in [State::FlightIdle, State::AntReady] {
+        let t = table.lookup(state, None, Event::FEUplinkLost).unwrap();
+        assert_eq!(t.to, None);
+        assert!(t.actions.contains(&Action::Retain));
+    }
+}
+
+#[test]
+fn ant_stop_escalation() {
+    let table = Table::default();
+
+    let drop_ble = |a: &Action| {
+        matches!(
+            a,
+            Action::SendRetry { command, on_success: Some(State::GarageOpen), .. }
+                if command.event == Event::CSBLEDisconnect
+        )
+    };
+
+    // stopping the ant escalates the same way whatever asked for it
+    for (state, event) in [
+        (State::AntRun, Event::FERoverMove),
+        (State::AntRun, Event::FEUplinkLost),
+        (State::AntRun, Event::FELimitTrip),
+    ] {
+        let t = table.lookup(state, None, event).unwrap();
+        assert_eq!(t.to, Some(State::AntStopping));
+
+        // walk the chain of failure handlers
+        let mut chain = vec![];
+        let mut actions = t.actions.as_slice();
+
+        while let Some(Action::SendRetry {
+            command,
+            on_success,
+            then,
+            on_failure,
+            ..
+        }) = actions.iter().find(|a| matches!(a, Action::SendRetry { .. }))
+        {
+            chain.push((command.event, *on_success));
+
+            // the alarm goes up once the escalated AntStop fails
+            if chain.len() == 2 {
+                assert!(on_failure.contains(&Action::Alarm {
+                    alarm: Alarm::StopUnconfirmed,
+                }));
+            }
+
+            // the BLE link is dropped whether or not the power-off is acked
+            if command.event == Event::AntPowerOff {
+                assert!(then.iter().any(drop_ble));
+                assert!(on_failure.iter().any(drop_ble));
+            }
+
+            actions = on_failure;
+        }
+
+        assert_eq!(chain, [
+            (Event::AntStop, Some(State::AntReady)),
+            (Event::AntStop, Some(State::AntReady)),
+            (Event::AntPowerOff, Some(State::BLEConnected)),
+            (Event::CSBLEDisconnect, Some(State::GarageOpen)),
+        ]);
+    }
+}
+
+#[test]
+fn dump_complete_restarts_ant() {
+    let table = Table::default();
+
+    let starts_ant = |t: &Transition| {
+        t.actions.iter().any(|a| {
+            matches!(a, Action::Later { actions, .. } if actions.iter().any(|a| matches!(
+                a,
+                Action::Request { command, .. } if command.event == Event::AntStart
+            )))
+        })
+    };
+
+    let t = table.lookup(State::AntRun, None, Event::FEDumpComplete).unwrap();
+    assert!(starts_ant(t));
+    assert!(!t.actions.contains(&Action::Retain));
+
+    // a dump finishing while the ant is being stopped mustn't start it again
+    for state in [State::AntStopping, State::AntReady, State::GarageOpen] {
+        let t = table.lookup(state, None, Event::FEDumpComplete).unwrap();
+        assert!(!starts_ant(t));
+        assert!(t.actions.contains(&Action::Retain));
+    }
+}
+
+#[test]
+fn ant_op_allowance() {
+    let table = Table::default();
+
+    let move_forward = table.op(Event::AntMoveForward).unwrap();
+    assert!(move_forward.allowed_in(State::AntRun));
+    assert!(!move_forward.allowed_in(State::AntReady));
+
+    let heater_on = table.op(Event::AntHeaterOn).unwrap();
+    assert!(!heater_on.allowed_in(State::GarageOpen));
+    assert!(heater_on.allowed_in(State::BLEConnected));
+
+    assert!(table.op(Event::AntPing).is_none());
+}
+
+#[test]
+fn rejects_duplicate_op() {
+    let mut table = Table::default();
+    let dup = table.ops[0].clone();
+    table.ops.push(dup);
+
+    assert!(matches!(table.validate(), Err(Error::DuplicateOp(Event::AntCalibrate))));
+}
+
+#[test]
+fn default_timing_is_valid() {
+    Timing::default().validate().unwrap();
+}
+
+#[test]
+fn rejects_bad_retry() {
+    let mut timing = Timing::default();
+
+    timing.garage_open_retry = Retry {
+        timeout:   Duration::from_secs(1),
+        base:      Duration::from_secs(10),
+        max_delay: Duration::from_secs(5),
+        count:     3,
+    };
+
+    assert_eq!(timing.validate().unwrap_err().name, "garage_open_retry");
+
+    timing.garage_open_retry.base = Duration::from_secs(1);
+    timing.garage_open_retry.count = 0;
+
+    assert!(timing.validate().is_err());
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use std::time::Duration;
+
+use actix::prelude::*;
+use actix_broker::{
+    Broker,
+    SystemBroker,
+};
+
+use message::{
+    downlink::{
+        ant_op,
+        thermostat::Report,
+    },
+    header::{
+        Destination,
+        Event,
+    },
+};
+use runtime::{
+    ground,
+    thermostat::{
+        Config,
+        Thermostat,
+    },
+    RunOp,
+    State,
+};
+
+use common::{
+    collect,
+    next,
+    settle,
+};
+
+fn config() -> Config {
+    Config {
+        setpoint: 100,
+        deadband: 5,
+        ..Config::default()
+    }
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+    config().validate().unwrap();
+}
+
+#[test]
+fn rejects_deadband_below_zero() {
+    let config = Config {
+        setpoint: 5,
+        deadband: 10,
+        ..Config::default()
+    };
+
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn heater_follows_deadband() {
+    let config = config();
+
+    assert_eq!(config.demand(94, None), Some(true));
+    assert_eq!(config.demand(94, Some(false)), Some(true));
+    assert_eq!(config.demand(94, Some(true)), None);
+
+    assert_eq!(config.demand(106, None), Some(false));
+    assert_eq!(config.demand(106, Some(true)), Some(false));
+    assert_eq!(config.demand(106, Some(false)), None);
+
+    // inside the deadband the heater is left alone, whatever it's doing
+    for temp in 95..=105 {
+        assert_eq!(config.demand(temp, None), None);
+        assert_eq!(config.demand(temp, Some(true)), None);
+        assert_eq!(config.demand(temp, Some(false)), None);
+    }
+}
+
+#[actix::test]
+async fn disabling_turns_heater_off() {
+    let mut reports = collect::<ground::ThermostatReport>();
+    let mut ops = collect::<RunOp>();
+    settle().await;
+
+    Thermostat::new(Config {
+        enabled: true,
+        ..config()
+    })
+    .start();
+    settle().await;
+
+    common::uplink(Event::FEThermostatOff, b"").await;
+
+    assert_eq!(next(&mut reports).await.0, Report::Disabled);
+    assert_eq!(next(&mut reports).await.0, Report::Heater {
+        on:          false,
+        temperature: None,
+    });
+
+    let RunOp(msg) = next(&mut ops).await;
+    assert_eq!(msg.header.header.ty.event, Event::AntHeaterOff);
+    assert_eq!(msg.header.header.destination, Destination::Ant);
+
+    // the state machine refuses it, which is reported once as ours
+    let rejected = ground::AntOpReport(ant_op::Report::Rejected {
+        event: Event::AntHeaterOff,
+        state: State::GarageOpen,
+    });
+
+    Broker::<SystemBroker>::issue_async(rejected.clone());
+    assert!(matches!(next(&mut reports).await.0, Report::Failed {
+        on: false,
+        ..
+    }));
+
+    Broker::<SystemBroker>::issue_async(rejected);
+    assert!(tokio::time::timeout(Duration::from_millis(50), reports.recv()).await.is_err());
+}
@@ -0,0 +1,118 @@
+use runtime::serial::watchdog::{
+    Config,
+    Tally,
+};
+
+/// Feed `results` through `config`, returning the tally after each.
+fn run(config: &Config, results: impl IntoIterator<Item = bool>) -> Vec<Tally> {
+    let mut missed = 0;
+
+    results
+        .into_iter()
+        .map(|acked| {
+            let tally = config.tally(missed, acked);
+            missed = tally.missed;
+
+            tally
+        })
+        .collect()
+}
+
+#[test]
+fn default_config_is_valid() {
+    Config::default().validate().unwrap();
+}
+
+#[test]
+fn rejects_bad_config() {
+    let config = Config {
+        missed_pings: 0,
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
+
+    let config = Config {
+        missed_pings: 4,
+        power_cycle_after: Some(2),
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
+
+    let config = Config {
+        power_cycle_after: None,
+        ..Default::default()
+    };
+    config.validate().unwrap();
+}
+
+#[test]
+fn counts_missed_pings() {
+    let config = Config::default();
+    let tallies = run(&config, [false, false, true, false]);
+
+    assert_eq!(tallies.iter().map(|t| t.missed).collect::<Vec<_>>(), [1, 2, 0, 1]);
+    assert!(tallies.iter().all(|t| *t
+        == Tally {
+            missed: t.missed,
+            ..Default::default()
+        }));
+}
+
+#[test]
+fn alarms_every_missed_pings() {
+    let config = Config {
+        missed_pings:      3,
+        reconnect:         true,
+        power_cycle_after: None,
+    };
+
+    let tallies = run(&config, [false; 9]);
+
+    let unresponsive = tallies.iter().filter(|t| t.unresponsive).map(|t| t.missed);
+    assert_eq!(unresponsive.collect::<Vec<_>>(), [3]);
+
+    let reconnects = tallies.iter().filter(|t| t.reconnect).map(|t| t.missed);
+    assert_eq!(reconnects.collect::<Vec<_>>(), [3, 6, 9]);
+
+    assert!(tallies.iter().all(|t| !t.power_cycle));
+
+    let quiet = Config {
+        reconnect: false,
+        ..config
+    };
+
+    let tallies = run(&quiet, [false; 9]);
+    assert!(tallies.iter().all(|t| !t.reconnect));
+    assert_eq!(tallies.iter().filter(|t| t.unresponsive).count(), 1);
+}
+
+#[test]
+fn requests_power_cycle_once() {
+    let config = Config {
+        missed_pings:      2,
+        reconnect:         true,
+        power_cycle_after: Some(5),
+    };
+
+    let tallies = run(&config, [false; 12]);
+
+    let power_cycles = tallies.iter().filter(|t| t.power_cycle).map(|t| t.missed);
+    assert_eq!(power_cycles.collect::<Vec<_>>(), [5]);
+}
+
+#[test]
+fn recovers_on_ack() {
+    let config = Config::default();
+
+    // acked before the alarm: nothing to recover from
+    let tallies = run(&config, [false, false, true]);
+    assert!(!tallies[2].recovered);
+
+    let tallies = run(&config, [false, false, false, false, true, false, false, false]);
+    assert!(tallies[4].recovered);
+    assert_eq!(tallies[4].missed, 0);
+
+    // counting starts over after recovery
+    assert!(tallies[7].unresponsive);
+    assert_eq!(tallies.iter().filter(|t| t.recovered).count(), 1);
+}
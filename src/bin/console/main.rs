@@ -1,8 +1,15 @@
-use std::ffi::OsString;
+use std::{
+    ffi::OsString,
+    path::PathBuf,
+    time::Duration,
+};
 
 use async_compat::CompatExt;
 use bytes::Bytes;
-use packed_struct::PackedStructSlice;
+use packed_struct::{
+    PackedStructSlice,
+    PrimitiveEnum,
+};
 use rustyline_async::ReadlineError;
 use structopt::StructOpt;
 use tap::Conv;
@@ -12,13 +19,17 @@ use tokio::io::{
 };
 
 use message::{
+    dfu,
     header::{
         Destination,
         Event,
     },
+    schedule,
+    sequence,
     BytesWrap,
     Downlink,
     Message,
+    MissionEpoch,
 };
 use net::{
     DatagramOps,
@@ -39,11 +50,22 @@ enum Command {
     GarageOpenPending,
     RoverStopping,
     RoverMoving,
+    CsPowerCycled,
     PingAnt,
     PingFrontend,
+    Status,
 
     Start,
 
+    ThermostatOn,
+    ThermostatOff,
+
+    AntDfu(Dfu),
+    CsDfu(Dfu),
+
+    Schedule(Schedule),
+    Sequence(Sequence),
+
     #[cfg(not(windows))]
     Reexec,
 
@@ -55,6 +77,131 @@ enum Command {
     Restart,
 }
 
+#[derive(structopt::StructOpt)]
+enum Dfu {
+    /// Uplink a firmware image to the frontend in chunks
+    Upload {
+        path: PathBuf,
+
+        #[structopt(long, default_value = "1024")]
+        chunk_size: usize,
+    },
+
+    /// Stream the uplinked image to its target
+    Flash {
+        /// Restart from the beginning instead of resuming
+        #[structopt(long)]
+        from_start: bool,
+    },
+
+    Abort,
+}
+
+#[derive(structopt::StructOpt)]
+enum Schedule {
+    /// Schedule a command for the ant or the central station
+    Add {
+        #[structopt(parse(try_from_str = parse_destination))]
+        destination: Destination,
+
+        #[structopt(parse(try_from_str = parse_event))]
+        event: Event,
+
+        /// Mission epoch (ms) at which to send the command
+        #[structopt(long, conflicts_with = "after", required_unless = "after")]
+        at: Option<u32>,
+
+        /// Seconds from now at which to send the command
+        #[structopt(long)]
+        after: Option<f64>,
+    },
+
+    /// List pending commands
+    List,
+
+    Cancel {
+        id: u32,
+    },
+
+    /// Drop all pending commands
+    Clear,
+}
+
+#[derive(structopt::StructOpt)]
+enum Sequence {
+    /// Store a sequence on the frontend, replacing any with the same name. Steps are one of:
+    /// `pause:<ms>`, `<dest>:<event>`, `<dest>:<event>:ack:<ms>[:<retries>]`,
+    /// `[<dest>:<event>:]await:<event>:<ms>`
+    Define {
+        name: String,
+
+        #[structopt(required = true, parse(try_from_str = parse_step))]
+        steps: Vec<sequence::Step>,
+    },
+
+    Delete {
+        name: String,
+    },
+
+    Run {
+        name: String,
+    },
+
+    /// Abort the running sequence
+    Abort,
+
+    /// List stored sequences
+    List,
+}
+
+/// Look up a [`PrimitiveEnum`] variant by its (case-insensitive) name.
+fn parse_named<T: PrimitiveEnum<Primitive = u8> + std::fmt::Debug>(s: &str) -> eyre::Result<T> {
+    (0..=u8::MAX)
+        .filter_map(T::from_primitive)
+        .find(|v| format!("{v:?}").eq_ignore_ascii_case(s))
+        .ok_or_else(|| eyre::eyre!("unknown value {s:?}"))
+}
+
+fn parse_destination(s: &str) -> eyre::Result<Destination> {
+    parse_named(s)
+}
+
+fn parse_event(s: &str) -> eyre::Result<Event> {
+    parse_named(s)
+}
+
+fn parse_step(s: &str) -> eyre::Result<sequence::Step> {
+    let parts = s.split(':').collect::<Vec<_>>();
+    let millis = |s: &str| -> eyre::Result<Duration> { Ok(Duration::from_millis(s.parse()?)) };
+
+    let (command, rest) = match parts[..] {
+        ["pause" | "await", ..] => (None, &parts[..]),
+        [dest, event, ref rest @ ..] => {
+            (Some((parse_destination(dest)?, parse_event(event)?)), rest)
+        },
+        _ => eyre::bail!("bad step {s:?}"),
+    };
+
+    let (wait, timeout, retries) = match *rest {
+        [] if command.is_some() => (sequence::Wait::Nothing, Duration::ZERO, 0),
+        ["pause", ms] if command.is_none() => (sequence::Wait::Nothing, millis(ms)?, 0),
+        ["ack", ms] => (sequence::Wait::Ack, millis(ms)?, 0),
+        ["ack", ms, retries] => (sequence::Wait::Ack, millis(ms)?, retries.parse()?),
+        ["await", event, ms] => (sequence::Wait::Event(parse_event(event)?), millis(ms)?, 0),
+        _ => eyre::bail!("bad step {s:?}"),
+    };
+
+    Ok(sequence::Step {
+        command,
+        wait,
+        timeout,
+        retries,
+    })
+}
+
+/// Delay between uplinked image chunks, so we don't overrun the relay.
+const CHUNK_INTERVAL: Duration = Duration::from_millis(20);
+
 /// Hacky way to reset the process if the relay has restarted (and so we need to reacquire the
 /// uplink socket.)
 #[cfg(not(windows))]
@@ -75,6 +222,10 @@ fn exec_self() -> ! {
 async fn main() -> eyre::Result<()> {
     let opts: Options = Options::from_args();
 
+    if let Some(ref path) = opts.calibration {
+        let _ = message::calibration::install(message::calibration::Calibration::load(path)?);
+    }
+
     let (mut rl, w) = rustyline_async::Readline::new("> ".to_owned())?;
     tokio::spawn({
         let w = w.clone().compat();
@@ -145,10 +296,45 @@ where
             Command::GarageOpenPending => Event::FEGarageOpen,
             Command::RoverStopping => Event::FERoverStop,
             Command::RoverMoving => Event::FERoverMove,
+            Command::CsPowerCycled => Event::FECSPowerCycled,
             Command::PingAnt => Event::AntPing,
             Command::PingFrontend => Event::FEPing,
+            Command::Status => Event::FEStatus,
             Command::Start => Event::AntStart,
 
+            Command::ThermostatOn => Event::FEThermostatOn,
+            Command::ThermostatOff => Event::FEThermostatOff,
+
+            Command::AntDfu(cmd) => {
+                if let Err(e) = send_dfu(&sock, Event::FEAntDFU, cmd).await {
+                    w.write_all(format!("ant dfu: {e}\n").as_bytes()).await?;
+                }
+
+                continue;
+            },
+            Command::CsDfu(cmd) => {
+                if let Err(e) = send_dfu(&sock, Event::FECSDFU, cmd).await {
+                    w.write_all(format!("cs dfu: {e}\n").as_bytes()).await?;
+                }
+
+                continue;
+            },
+
+            Command::Schedule(cmd) => {
+                if let Err(e) = send_schedule(&sock, cmd).await {
+                    w.write_all(format!("schedule: {e}\n").as_bytes()).await?;
+                }
+
+                continue;
+            },
+            Command::Sequence(cmd) => {
+                if let Err(e) = send_sequence(&sock, cmd).await {
+                    w.write_all(format!("sequence: {e}\n").as_bytes()).await?;
+                }
+
+                continue;
+            },
+
             #[cfg(not(windows))]
             Command::Reexec => exec_self(),
 
@@ -166,6 +352,129 @@ where
     }
 }
 
+async fn send_schedule(sock: &antrelay::Socket, cmd: Schedule) -> eyre::Result<()> {
+    let cmd = match cmd {
+        Schedule::Add {
+            destination,
+            event,
+            at,
+            after,
+        } => {
+            let when = match (at, after) {
+                (Some(at), _) => schedule::When::At(MissionEpoch::from(at)),
+                (None, Some(secs)) => schedule::When::After(Duration::try_from_secs_f64(secs)?),
+                (None, None) => eyre::bail!("one of --at or --after is required"),
+            };
+
+            schedule::Command::Add {
+                when,
+                destination,
+                event,
+                payload: Bytes::new(),
+            }
+        },
+        Schedule::List => schedule::Command::List,
+        Schedule::Cancel {
+            id,
+        } => schedule::Command::Cancel {
+            id,
+        },
+        Schedule::Clear => schedule::Command::Clear,
+    };
+
+    let msg = message::command_with_payload(
+        &runtime::params().await,
+        Destination::Frontend,
+        Event::FESchedule,
+        cmd.pack_to_vec()?,
+    );
+
+    sock.send(&msg.pack_to_vec()?).await?;
+
+    Ok(())
+}
+
+async fn send_sequence(sock: &antrelay::Socket, cmd: Sequence) -> eyre::Result<()> {
+    let cmd = match cmd {
+        Sequence::Define {
+            name,
+            steps,
+        } => sequence::Command::Define {
+            name,
+            steps,
+        },
+        Sequence::Delete {
+            name,
+        } => sequence::Command::Delete {
+            name,
+        },
+        Sequence::Run {
+            name,
+        } => sequence::Command::Run {
+            name,
+        },
+        Sequence::Abort => sequence::Command::Abort,
+        Sequence::List => sequence::Command::List,
+    };
+
+    let msg = message::command_with_payload(
+        &runtime::params().await,
+        Destination::Frontend,
+        Event::FESequence,
+        cmd.pack_to_vec()?,
+    );
+
+    sock.send(&msg.pack_to_vec()?).await?;
+
+    Ok(())
+}
+
+async fn send_dfu(sock: &antrelay::Socket, event: Event, cmd: Dfu) -> eyre::Result<()> {
+    let cmds = match cmd {
+        Dfu::Upload {
+            path,
+            chunk_size,
+        } => {
+            eyre::ensure!(chunk_size > 0, "chunk size must be nonzero");
+
+            let image = tokio::fs::read(&path).await?;
+
+            let begin = dfu::Command::Begin(dfu::ImageInfo {
+                size:     image.len().try_into()?,
+                checksum: dfu::checksum(&image),
+            });
+
+            let chunks =
+                image.chunks(chunk_size).enumerate().map(|(i, chunk)| dfu::Command::Chunk {
+                    offset: (i * chunk_size) as u32,
+                    data:   Bytes::copy_from_slice(chunk),
+                });
+
+            std::iter::once(begin).chain(chunks).collect::<Vec<_>>()
+        },
+        Dfu::Flash {
+            from_start,
+        } => vec![dfu::Command::Flash {
+            from_start,
+        }],
+        Dfu::Abort => vec![dfu::Command::Abort],
+    };
+
+    for cmd in cmds {
+        let msg = message::command_with_payload(
+            &runtime::params().await,
+            Destination::Frontend,
+            event,
+            cmd.pack_to_vec()?,
+        );
+
+        sock.send(&msg.pack_to_vec()?).await?;
+        tokio::time::sleep(CHUNK_INTERVAL).await;
+    }
+
+    Ok(())
+}
+
 async fn read_downlink<Socket>(
     downlink: Socket,
     mut output: impl AsyncWrite + Unpin,
@@ -193,6 +502,21 @@ where
             Downlink::SerialDownlink(m) => msg_format("SERIAL DOWN (MSG)", m),
             Downlink::SerialUplink(m) => msg_format("SERIAL UP (MSG)", m),
             Downlink::UplinkInterpreted(m) => msg_format("UPLINK (MSG)", m),
+
+            Downlink::StateMachine(r) => format!("STATE MACHINE\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::AntDfu(r) => format!("ANT DFU\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::CsDfu(r) => format!("CS DFU\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::Link(r) => format!("LINK\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::AntOp(r) => format!("ANT OP\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::Schedule(r) => format!("SCHEDULE\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::Sequence(r) => format!("SEQUENCE\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::Limits(r) => format!("LIMITS\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::Thermostat(r) => format!("THERMOSTAT\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::Dump(r) => format!("DUMP\n\t{r}\n").as_bytes().to_vec(),
+            Downlink::Telemetry(t) => format!("TELEMETRY\n\t{t}\n").as_bytes().to_vec(),
+            Downlink::Status(s) => format!("STATUS\n\t{s}\n").as_bytes().to_vec(),
+            Downlink::Lifecycle(h) => format!("LIFECYCLE\n\t{h}\n").as_bytes().to_vec(),
+            Downlink::Queue(r) => format!("QUEUE\n\t{r}\n").as_bytes().to_vec(),
         };
 
         line.extend_from_slice(b"\n\n");
@@ -212,5 +536,8 @@ fn bytes_format(msg: impl AsRef<str>, b: BytesWrap) -> Vec<u8> {
 }
 
 fn msg_format(msg: impl AsRef<str>, m: Message) -> Vec<u8> {
-    format!("{}\n\t{}\n", msg.as_ref(), m).as_bytes().to_vec()
+    match message::relay(&m) {
+        Some(relay) => format!("{}\n\t{}\n\t{}\n", msg.as_ref(), m, relay).as_bytes().to_vec(),
+        None => format!("{}\n\t{}\n", msg.as_ref(), m).as_bytes().to_vec(),
+    }
 }
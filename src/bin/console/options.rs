@@ -19,4 +19,10 @@ pub struct Options {
         )
     )]
     pub downlink: antrelay::Address,
+
+    #[structopt(
+        long,
+        help = "path to a JSON telemetry calibration file (optional, telemetry is shown raw without one)"
+    )]
+    pub calibration: Option<std::path::PathBuf>,
 }
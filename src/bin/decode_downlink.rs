@@ -9,6 +9,7 @@ use tap::Conv;
 
 use message::{
     self,
+    calibration,
     Downlink,
 };
 
@@ -23,11 +24,21 @@ pub struct Mode {
 
     #[structopt(long, help = "interpret stdin as base64")]
     base64: bool,
+
+    #[structopt(
+        long,
+        help = "path to a JSON telemetry calibration file (optional, telemetry is shown raw without one)"
+    )]
+    calibration: Option<std::path::PathBuf>,
 }
 
 fn main() -> eyre::Result<()> {
     let mode = Mode::from_args();
 
+    if let Some(ref path) = mode.calibration {
+        let _ = calibration::install(calibration::Calibration::load(path)?);
+    }
+
     let buf = match mode {
         Mode {
             hex: true,
@@ -61,7 +72,10 @@ fn main() -> eyre::Result<()> {
     match msg {
         Downlink::Log(b) => println!("LOG\n\t{b:?}"),
 
-        Downlink::SerialDownlink(m) => println!("SERIAL DOWNLINK\n\t{m}"),
+        Downlink::SerialDownlink(m) => match message::relay(&m) {
+            Some(relay) => println!("SERIAL DOWNLINK\n\t{m}\n\t{relay}"),
+            None => println!("SERIAL DOWNLINK\n\t{m}"),
+        },
         Downlink::SerialUplink(m) => println!("SERIAL UPLINK\n\t{m}"),
         Downlink::UplinkInterpreted(m) => println!("UPLINK ECHO\n\t{m}"),
 
@@ -74,6 +88,21 @@ fn main() -> eyre::Result<()> {
         Downlink::UplinkMirror(b) => {
             println!("UPLINK ECHO (RAW)\n\t{}", hex::encode(b.conv::<Bytes>()))
         },
+
+        Downlink::StateMachine(r) => println!("STATE MACHINE\n\t{r}"),
+        Downlink::AntDfu(r) => println!("ANT DFU\n\t{r}"),
+        Downlink::CsDfu(r) => println!("CS DFU\n\t{r}"),
+        Downlink::Link(r) => println!("LINK\n\t{r}"),
+        Downlink::AntOp(r) => println!("ANT OP\n\t{r}"),
+        Downlink::Schedule(r) => println!("SCHEDULE\n\t{r}"),
+        Downlink::Sequence(r) => println!("SEQUENCE\n\t{r}"),
+        Downlink::Limits(r) => println!("LIMITS\n\t{r}"),
+        Downlink::Thermostat(r) => println!("THERMOSTAT\n\t{r}"),
+        Downlink::Dump(r) => println!("DUMP\n\t{r}"),
+        Downlink::Telemetry(t) => println!("TELEMETRY\n\t{t}"),
+        Downlink::Status(s) => println!("STATUS\n\t{s}"),
+        Downlink::Lifecycle(h) => println!("LIFECYCLE\n\t{h}"),
+        Downlink::Queue(r) => println!("QUEUE\n\t{r}"),
     }
 
     Ok(())
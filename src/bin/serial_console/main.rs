@@ -33,17 +33,16 @@ use tokio::{
     io::{
         AsyncWrite,
         AsyncWriteExt,
-        WriteHalf,
     },
     sync::Mutex,
 };
-use tokio_serial::SerialStream;
 use tokio_util::codec::{
     Decoder,
     FramedRead,
     FramedWrite,
 };
 
+use antrelay::transport;
 use message::{
     source_info::Info,
     BytesWrap,
@@ -87,10 +86,7 @@ enum Command {
 async fn main() -> eyre::Result<()> {
     let opts: Options = Options::from_args();
 
-    let builder = tokio_serial::new(opts.port, opts.baud);
-    let stream = tokio_serial::SerialStream::open(&builder)?;
-
-    let (reader, writer) = tokio::io::split(stream);
+    let (reader, writer) = opts.port.open(opts.baud).await?;
 
     let framed_read = FramedRead::new(reader, CobsCodec);
     let framed_write = FramedWrite::new(writer, CobsCodec);
@@ -291,7 +287,7 @@ async fn main() -> eyre::Result<()> {
 async fn read_uplink(
     mut uplink: impl Stream<Item = Result<Result<Bytes, codec::cobs::Error>, std::io::Error>> + Unpin,
     mut output: impl AsyncWrite + Unpin,
-    downlink: Arc<Mutex<FramedWrite<WriteHalf<SerialStream>, CobsCodec>>>,
+    downlink: Arc<Mutex<FramedWrite<transport::Writer, CobsCodec>>>,
 ) -> eyre::Result<()> {
     loop {
         output.flush().await?;
@@ -1,10 +1,24 @@
 #[derive(Debug, Clone, PartialEq, Eq, structopt::StructOpt)]
 pub struct Options {
     #[structopt(long = "serial_port", required = true)]
-    #[cfg_attr(unix, structopt(help = "path to serial port"))]
-    #[cfg_attr(windows, structopt(help = "serial port (e.g. COM8)"))]
-    pub port: String,
+    #[cfg_attr(
+        unix,
+        structopt(
+            help = "serial transport: a path to a serial port, or a URL (tty:///dev/ttyUSB0?baud=115200&parity=none&flow=none, tcp://127.0.0.1:7000, unix:///tmp/cs.sock, or pty)"
+        )
+    )]
+    #[cfg_attr(
+        windows,
+        structopt(
+            help = "serial transport: a serial port (e.g. COM8), or a URL (tty://COM8?baud=115200&parity=none&flow=none, or tcp://127.0.0.1:7000)"
+        )
+    )]
+    pub port: antrelay::transport::Transport,
 
-    #[structopt(long = "baud", default_value = "115200", help = "serial baud rate (optional)")]
+    #[structopt(
+        long = "baud",
+        default_value = "115200",
+        help = "serial baud rate (optional, overridden by a baud in the serial transport URL)"
+    )]
     pub baud: u32,
 }
@@ -8,6 +8,8 @@ use std::{
 
 use net::DatagramOps;
 
+pub mod transport;
+
 #[cfg(windows)]
 pub type Socket = tokio::net::UdpSocket;
 
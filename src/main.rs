@@ -16,6 +16,7 @@ use actix::{
 };
 use structopt::StructOpt as _;
 
+use message::payload::log::Type;
 use net::{
     DatagramOps,
     DatagramReceiver,
@@ -56,32 +57,71 @@ fn main() -> std::io::Result<()> {
         "tracing subsystem initialized"
     );
 
+    ground::downlink::lifecycle(Type::Startup);
+
+    let config = match options.config {
+        Some(ref path) => runtime::config::Config::load(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?,
+        None => Default::default(),
+    };
+
+    config.log();
+
+    if let Some(ref path) = options.calibration {
+        let calibration = message::calibration::Calibration::load(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+
+        tracing::info!(?calibration, "telemetry calibration loaded");
+        let _ = message::calibration::install(calibration);
+    }
+
+    let table = match options.transition_table {
+        Some(ref path) => runtime::table::Table::load(path)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?,
+        None => Default::default(),
+    };
+
     let sys = System::new();
 
     sys.block_on(async {
         trace::downlink::ACTIVE.store(true, Ordering::SeqCst);
 
         Supervisor::start(|_ctx| serial::ant_decode::AntDecode::default());
-        Supervisor::start(|_ctx| runtime::StateMachine::default());
+        let ops = table.ops.iter().map(|op| op.event).collect::<Vec<_>>();
+        let state_journal = options.state_journal.clone();
+        Supervisor::start(move |_ctx| runtime::StateMachine::new(state_journal, table, config.timing));
         Supervisor::start(|_ctx| serial::Serial::default());
+        let baud = options.serial_port.baud().unwrap_or(options.baud);
+        Supervisor::start(move |_ctx| serial::queue::SerialQueue::new(config.serial_queue, baud));
+        Supervisor::start(move |_ctx| serial::watchdog::Watchdog::new(config.watchdog));
+        Supervisor::start(move |_ctx| runtime::dfu::ant::AntDfu::new(config.dfu));
+        Supervisor::start(move |_ctx| runtime::dfu::cs::CsDfu::new(config.dfu));
+        let limit_ops = ops.clone();
+        Supervisor::start(move |_ctx| runtime::scheduler::Scheduler::new(config.scheduler, ops));
+        Supervisor::start(move |_ctx| runtime::limits::Limits::new(config.limits, limit_ops));
+        Supervisor::start(move |_ctx| runtime::thermostat::Thermostat::new(config.thermostat));
+        Supervisor::start(move |_ctx| runtime::dump::Dumps::new(config.dump));
+        Supervisor::start(move |_ctx| runtime::housekeeping::Housekeeping::new(config.housekeeping));
+        let sequence_store = options.sequence_store.clone();
+        Supervisor::start(move |_ctx| {
+            runtime::sequencer::Sequencer::new(sequence_store, config.sequencer)
+        });
+
+        let transport = options.serial_port.clone();
+        let device = transport.device();
 
         Supervisor::start(move |_ctx| {
-            serial::raw::RawIO::new(Box::new(move || {
-                let port = options.serial_port.clone();
+            serial::raw::RawIO::new(config.serial, device, Box::new(move || {
+                let transport = transport.clone();
 
                 Box::pin(async move {
-                    let ser = tokio_serial::new(&port, options.baud);
-
-                    match tokio_serial::SerialStream::open(&ser) {
-                        Ok(s) => {
-                            let (r, w) = tokio::io::split(s);
-                            Some((
-                                Box::new(r) as Box<dyn tokio::io::AsyncRead + Unpin + 'static>,
-                                Box::new(w) as Box<dyn tokio::io::AsyncWrite + Unpin + 'static>,
-                            ))
-                        },
+                    match transport.open(baud).await {
+                        Ok((r, w)) => Some((
+                            r as Box<dyn tokio::io::AsyncRead + Unpin + 'static>,
+                            w as Box<dyn tokio::io::AsyncWrite + Unpin + 'static>,
+                        )),
                         Err(e) => {
-                            tracing::error!(error = %e, "connecting to serial port");
+                            tracing::error!(%transport, error = %e, "connecting to serial port");
                             None
                         },
                     }
@@ -113,6 +153,7 @@ fn main() -> std::io::Result<()> {
                 })
             }),
         });
+        Supervisor::start(move |_ctx| ground::uplink::Heartbeat::new(config.uplink));
 
         connect_once(&options.downlink_addresses).await;
 
@@ -134,6 +175,23 @@ fn main() -> std::io::Result<()> {
                     }))
                 });
             });
+
+        ground::downlink::lifecycle(Type::RelayStarted);
+
+        actix::spawn(async {
+            if let Err(e) = tokio::signal::ctrl_c().await {
+                tracing::error!(error = %e, "listening for interrupt");
+                return;
+            }
+
+            tracing::warn!("interrupted, shutting down");
+
+            ground::downlink::lifecycle(Type::Interrupted);
+            ground::downlink::lifecycle(Type::Shutdown);
+
+            tokio::time::sleep(ground::downlink::SHUTDOWN_GRACE).await;
+            System::current().stop();
+        });
     });
 
     sys.run()
@@ -21,13 +21,55 @@ pub struct Options {
     pub uplink_address: antrelay::Address,
 
     #[structopt(short, long)]
-    #[cfg_attr(unix, structopt(help = "path to serial port"))]
-    #[cfg_attr(windows, structopt(help = "serial port (e.g. COM8)"))]
-    pub serial_port: String,
+    #[cfg_attr(
+        unix,
+        structopt(
+            help = "serial transport: a path to a serial port, or a URL (tty:///dev/ttyUSB0?baud=115200&parity=none&flow=none, tcp://127.0.0.1:7000, unix:///tmp/cs.sock, or pty)"
+        )
+    )]
+    #[cfg_attr(
+        windows,
+        structopt(
+            help = "serial transport: a serial port (e.g. COM8), or a URL (tty://COM8?baud=115200&parity=none&flow=none, or tcp://127.0.0.1:7000)"
+        )
+    )]
+    pub serial_port: antrelay::transport::Transport,
 
-    #[structopt(short, long, default_value = "115200", help = "serial baud rate (optional)")]
+    #[structopt(
+        short,
+        long,
+        default_value = "115200",
+        help = "serial baud rate (optional, overridden by a baud in the serial transport URL)"
+    )]
     pub baud: u32,
 
+    #[structopt(
+        long,
+        help = "path to a JSON runtime configuration file (optional, all sections default)"
+    )]
+    pub config: Option<std::path::PathBuf>,
+
+    #[structopt(
+        long,
+        help = "path to the state machine journal, used to resume after a restart (optional)"
+    )]
+    pub state_journal: Option<std::path::PathBuf>,
+
+    #[structopt(long, help = "path to persist stored command sequences to (optional)")]
+    pub sequence_store: Option<std::path::PathBuf>,
+
+    #[structopt(
+        long,
+        help = "path to a JSON state machine transition table (optional, defaults to the built-in table)"
+    )]
+    pub transition_table: Option<std::path::PathBuf>,
+
+    #[structopt(
+        long,
+        help = "path to a JSON telemetry calibration file (optional, telemetry is shown raw without one)"
+    )]
+    pub calibration: Option<std::path::PathBuf>,
+
     #[structopt(long, help = "pretty log output")]
     pub pretty: bool,
 }
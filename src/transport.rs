@@ -0,0 +1,451 @@
+//! Serial transports for talking to the central station: a real serial port, or (for bench
+//! testing) a TCP or unix stream socket or a pty.
+
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+    },
+    io,
+    path::PathBuf,
+    pin::Pin,
+    str::FromStr,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+use tokio::io::{
+    AsyncRead,
+    AsyncWrite,
+    ReadBuf,
+};
+use tokio_serial::{
+    FlowControl,
+    Parity,
+    SerialStream,
+};
+
+pub type Reader = Box<dyn AsyncRead + Unpin + Send>;
+pub type Writer = Box<dyn AsyncWrite + Unpin + Send>;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum Error {
+    #[error("unknown transport scheme '{0}' (expected tty, tcp, unix or pty)")]
+    Scheme(String),
+
+    #[error("missing {0}")]
+    Missing(&'static str),
+
+    #[error("unknown {scheme} parameter '{param}'")]
+    Param {
+        scheme: &'static str,
+        param:  String,
+    },
+
+    #[error("invalid {param} '{value}'")]
+    Value {
+        param: &'static str,
+        value: String,
+    },
+
+    #[error("{0} transport not supported on this platform")]
+    Unsupported(&'static str),
+}
+
+/// Where to find the central station. Parsed from a URL:
+///
+/// - `tty:///dev/ttyUSB0?baud=115200&parity=none&flow=none` (or just a bare path, e.g.
+///   `/dev/ttyUSB0` or `COM8`)
+/// - `tcp://127.0.0.1:7000`
+/// - `unix:///tmp/cs.sock`
+/// - `pty`: create a pty and print the path of its other end. The pty is kept for the life of the
+///   process, so reconnecting reuses it and the path stays valid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transport {
+    Tty {
+        path:   String,
+        /// Overrides the baud rate passed to [`Transport::open`].
+        baud:   Option<u32>,
+        parity: Parity,
+        flow:   FlowControl,
+    },
+    Tcp(String),
+    Unix(PathBuf),
+    Pty,
+}
+
+impl Transport {
+    /// The baud rate given in the URL, if any.
+    pub fn baud(&self) -> Option<u32> {
+        match self {
+            Transport::Tty {
+                baud,
+                ..
+            } => *baud,
+            _ => None,
+        }
+    }
+
+    /// The filesystem node backing this transport, which may be watched for reappearance after the
+    /// connection drops.
+    pub fn device(&self) -> Option<PathBuf> {
+        match self {
+            Transport::Tty {
+                path,
+                ..
+            } if cfg!(unix) => Some(PathBuf::from(path)),
+            Transport::Unix(path) => Some(path.clone()),
+            _ => None,
+        }
+    }
+
+    /// Connect, using `baud` if the URL doesn't specify a baud rate.
+    pub async fn open(&self, baud: u32) -> io::Result<(Reader, Writer)> {
+        match self {
+            Transport::Tty {
+                path,
+                baud: url_baud,
+                parity,
+                flow,
+            } => {
+                let builder = tokio_serial::new(path, url_baud.unwrap_or(baud))
+                    .parity(*parity)
+                    .flow_control(*flow);
+
+                Ok(split(SerialStream::open(&builder)?))
+            },
+            Transport::Tcp(addr) => {
+                let stream = tokio::net::TcpStream::connect(addr).await?;
+                stream.set_nodelay(true)?;
+
+                Ok(split(stream))
+            },
+            #[cfg(unix)]
+            Transport::Unix(path) => Ok(split(tokio::net::UnixStream::connect(path).await?)),
+            #[cfg(unix)]
+            Transport::Pty => {
+                let idle = PTY.lock().unwrap_or_else(|e| e.into_inner()).take();
+
+                // none yet, or the last connection still holds it
+                let pty = match idle {
+                    Some(pty) => pty,
+                    None => Pty::new()?,
+                };
+
+                tracing::info!(path = %pty.path, "opened pty");
+
+                Ok(split(Lease(Some(pty))))
+            },
+            #[cfg(not(unix))]
+            Transport::Unix(_) | Transport::Pty => {
+                Err(io::Error::new(io::ErrorKind::Unsupported, self.to_string()))
+            },
+        }
+    }
+}
+
+fn split(stream: impl AsyncRead + AsyncWrite + Send + 'static) -> (Reader, Writer) {
+    let (r, w) = tokio::io::split(stream);
+
+    (Box::new(r), Box::new(w))
+}
+
+fn parse_parity(value: &str) -> Result<Parity, Error> {
+    match value {
+        "none" => Ok(Parity::None),
+        "odd" => Ok(Parity::Odd),
+        "even" => Ok(Parity::Even),
+        _ => Err(Error::Value {
+            param: "parity",
+            value: value.to_owned(),
+        }),
+    }
+}
+
+fn parse_flow(value: &str) -> Result<FlowControl, Error> {
+    match value {
+        "none" => Ok(FlowControl::None),
+        "software" => Ok(FlowControl::Software),
+        "hardware" => Ok(FlowControl::Hardware),
+        _ => Err(Error::Value {
+            param: "flow",
+            value: value.to_owned(),
+        }),
+    }
+}
+
+fn parse_tty(rest: &str) -> Result<Transport, Error> {
+    let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+    if path.is_empty() {
+        return Err(Error::Missing("tty path"));
+    }
+
+    let mut baud = None;
+    let mut parity = Parity::None;
+    let mut flow = FlowControl::None;
+
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+        match key {
+            "baud" => {
+                baud = Some(value.parse().map_err(|_| Error::Value {
+                    param: "baud",
+                    value: value.to_owned(),
+                })?)
+            },
+            "parity" => parity = parse_parity(value)?,
+            "flow" => flow = parse_flow(value)?,
+            _ => {
+                return Err(Error::Param {
+                    scheme: "tty",
+                    param:  key.to_owned(),
+                });
+            },
+        }
+    }
+
+    Ok(Transport::Tty {
+        path: path.to_owned(),
+        baud,
+        parity,
+        flow,
+    })
+}
+
+impl FromStr for Transport {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "pty" || s == "pty://" {
+            return if cfg!(unix) {
+                Ok(Transport::Pty)
+            } else {
+                Err(Error::Unsupported("pty"))
+            };
+        }
+
+        // anything that isn't a URL is a serial port, as before transports existed
+        let Some((scheme, rest)) = s.split_once("://") else {
+            return parse_tty(s);
+        };
+
+        match scheme {
+            "tty" => parse_tty(rest),
+            "tcp" if rest.is_empty() => Err(Error::Missing("tcp address")),
+            "tcp" => Ok(Transport::Tcp(rest.to_owned())),
+            "unix" if !cfg!(unix) => Err(Error::Unsupported("unix")),
+            "unix" if rest.is_empty() => Err(Error::Missing("unix socket path")),
+            "unix" => Ok(Transport::Unix(PathBuf::from(rest))),
+            _ => Err(Error::Scheme(scheme.to_owned())),
+        }
+    }
+}
+
+impl Display for Transport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Transport::Tty {
+                path,
+                baud,
+                parity,
+                flow,
+            } => {
+                write!(f, "tty://{path}?")?;
+
+                if let Some(baud) = baud {
+                    write!(f, "baud={baud}&")?;
+                }
+
+                let parity = match parity {
+                    Parity::None => "none",
+                    Parity::Odd => "odd",
+                    Parity::Even => "even",
+                };
+
+                let flow = match flow {
+                    FlowControl::None => "none",
+                    FlowControl::Software => "software",
+                    FlowControl::Hardware => "hardware",
+                };
+
+                write!(f, "parity={parity}&flow={flow}")
+            },
+            Transport::Tcp(addr) => write!(f, "tcp://{addr}"),
+            Transport::Unix(path) => write!(f, "unix://{}", path.display()),
+            Transport::Pty => write!(f, "pty"),
+        }
+    }
+}
+
+/// One end of a pty pair. The other end is held open so that reads don't fail before anything
+/// else has opened it.
+#[cfg(unix)]
+struct Pty {
+    ours:    SerialStream,
+    _theirs: SerialStream,
+    /// Path of the other end.
+    path:    String,
+}
+
+/// The pty between connections: it's created by the first [`Transport::open`] and put back here
+/// when that connection is dropped, for the next one to pick up.
+#[cfg(unix)]
+static PTY: std::sync::Mutex<Option<Pty>> = std::sync::Mutex::new(None);
+
+#[cfg(unix)]
+impl Pty {
+    fn new() -> io::Result<Self> {
+        let (ours, theirs) = SerialStream::pair()?;
+
+        let path = tokio_serial::SerialPort::name(&theirs).unwrap_or_default();
+        tracing::info!(%path, "created pty");
+        println!("{path}");
+
+        Ok(Self {
+            ours,
+            _theirs: theirs,
+            path,
+        })
+    }
+}
+
+/// The pty, on loan to a connection. Returned to [`PTY`] on drop.
+#[cfg(unix)]
+struct Lease(Option<Pty>);
+
+#[cfg(unix)]
+impl Lease {
+    #[inline]
+    fn pty(&mut self) -> Pin<&mut Pty> {
+        Pin::new(self.0.as_mut().expect("pty only taken on drop"))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Lease {
+    fn drop(&mut self) {
+        *PTY.lock().unwrap_or_else(|e| e.into_inner()) = self.0.take();
+    }
+}
+
+#[cfg(unix)]
+impl AsyncRead for Lease {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        self.pty().poll_read(cx, buf)
+    }
+}
+
+#[cfg(unix)]
+impl AsyncWrite for Lease {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.pty().poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.pty().poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.pty().poll_shutdown(cx)
+    }
+}
+
+#[cfg(unix)]
+impl AsyncRead for Pty {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.ours).poll_read(cx, buf)
+    }
+}
+
+#[cfg(unix)]
+impl AsyncWrite for Pty {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.ours).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.ours).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.ours).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_urls() {
+        assert_eq!(
+            "tty:///dev/ttyUSB0?baud=9600&parity=even&flow=hardware".parse(),
+            Ok(Transport::Tty {
+                path:   "/dev/ttyUSB0".to_owned(),
+                baud:   Some(9600),
+                parity: Parity::Even,
+                flow:   FlowControl::Hardware,
+            })
+        );
+
+        assert_eq!(
+            "COM8".parse(),
+            Ok(Transport::Tty {
+                path:   "COM8".to_owned(),
+                baud:   None,
+                parity: Parity::None,
+                flow:   FlowControl::None,
+            })
+        );
+
+        assert_eq!("tcp://127.0.0.1:7000".parse(), Ok(Transport::Tcp("127.0.0.1:7000".to_owned())));
+
+        #[cfg(unix)]
+        {
+            assert_eq!("unix:///tmp/cs.sock".parse(), Ok(Transport::Unix("/tmp/cs.sock".into())));
+            assert_eq!("pty".parse(), Ok(Transport::Pty));
+        }
+    }
+
+    #[test]
+    fn rejects_bad_urls() {
+        assert!(matches!("ftp://host".parse::<Transport>(), Err(Error::Scheme(_))));
+        assert!(matches!("tcp://".parse::<Transport>(), Err(Error::Missing(_))));
+        assert!(matches!("tty://?baud=9600".parse::<Transport>(), Err(Error::Missing(_))));
+        assert!(matches!(
+            "tty:///dev/ttyS0?baud=fast".parse::<Transport>(),
+            Err(Error::Value { .. })
+        ));
+        assert!(matches!("tty:///dev/ttyS0?stop=2".parse::<Transport>(), Err(Error::Param { .. })));
+    }
+
+    #[test]
+    fn display_round_trips() {
+        for url in
+            ["tty:///dev/ttyUSB0?baud=115200&parity=odd&flow=software", "tcp://localhost:7000"]
+        {
+            let transport = url.parse::<Transport>().unwrap();
+            assert_eq!(transport.to_string().parse(), Ok(transport));
+        }
+    }
+}